@@ -0,0 +1,41 @@
+use tonic_build::manual::{Builder, Method, Service};
+
+// The grpc service is generated for the protobuf messages of claims-schema (rust-protobuf rather
+// than prost), see `resources/api/claims_api.proto` there and `api::grpc::codec`
+fn main() {
+    // With a build script sqlx needs to be told to embed changed migrations
+    println!("cargo:rerun-if-changed=migrations");
+
+    #[rustfmt::skip]
+    let methods = [
+        ("list_claims", "ListClaims", "claims_api::ListClaimsRequest", "claims_api::ListClaimsResponse"),
+        ("get_claim", "GetClaim", "claims_api::GetClaimRequest", "claims_api::ClaimDetails"),
+        ("create_claim", "CreateClaim", "claims_api::CreateClaimRequest", "claim::Claim"),
+        ("update_claim", "UpdateClaim", "claims_api::UpdateClaimRequest", "claim::Claim"),
+        ("list_parties", "ListParties", "claims_api::ListPartiesRequest", "claims_api::ListPartiesResponse"),
+        ("get_party", "GetParty", "claims_api::GetPartyRequest", "party::Party"),
+        ("add_party", "AddParty", "claims_api::AddPartyRequest", "party::Party"),
+        ("update_party", "UpdateParty", "claims_api::UpdatePartyRequest", "party::Party"),
+        ("remove_party", "RemoveParty", "claims_api::RemovePartyRequest", "party::Party"),
+    ];
+
+    let service = methods
+        .into_iter()
+        .fold(
+            Service::builder().name("ClaimsApi").package("claims.api"),
+            |service, (name, route_name, input, output)| {
+                service.method(
+                    Method::builder()
+                        .name(name)
+                        .route_name(route_name)
+                        .input_type(format!("claims_model::model::proto::{input}"))
+                        .output_type(format!("claims_model::model::proto::{output}"))
+                        .codec_path("crate::api::grpc::codec::ProtobufCodec")
+                        .build(),
+                )
+            },
+        )
+        .build();
+
+    Builder::new().compile(&[service]);
+}
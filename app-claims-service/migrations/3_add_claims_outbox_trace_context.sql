@@ -0,0 +1,4 @@
+-- W3C trace context of the request that produced the event
+-- routed by debezium to the kafka message headers (see the connector additional placement)
+ALTER TABLE claim_outbox_event ADD COLUMN IF NOT EXISTS traceparent VARCHAR(55);
+ALTER TABLE claim_outbox_event ADD COLUMN IF NOT EXISTS tracestate VARCHAR(512);
@@ -0,0 +1,3 @@
+-- Correlation id of the user action that produced the event
+-- routed by debezium to the kafka message headers (see the connector additional placement)
+ALTER TABLE claim_outbox_event ADD COLUMN IF NOT EXISTS correlation_id VARCHAR(255);
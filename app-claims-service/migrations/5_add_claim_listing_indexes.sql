@@ -0,0 +1,7 @@
+-- Indexes backing the filters of the claims listing
+CREATE INDEX IF NOT EXISTS idx_claim_status ON claim (status);
+CREATE INDEX IF NOT EXISTS idx_claim_incident_type ON claim (incident_type);
+-- Prefix search on claim_no (LIKE 'prefix%') regardless of the database collation
+CREATE INDEX IF NOT EXISTS idx_claim_claim_no_pattern ON claim (claim_no varchar_pattern_ops);
+-- Parties are always read by claim
+CREATE INDEX IF NOT EXISTS idx_party_claim_id ON party (claim_id);
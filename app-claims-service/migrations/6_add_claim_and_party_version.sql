@@ -0,0 +1,3 @@
+-- Row versions for optimistic concurrency, incremented on every update
+ALTER TABLE claim ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE party ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
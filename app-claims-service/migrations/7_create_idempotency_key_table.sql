@@ -0,0 +1,12 @@
+-- Responses of the create requests sent with an Idempotency-Key header,
+-- a repeated request (same key and scope) is answered with the stored response
+CREATE TABLE IF NOT EXISTS idempotency_key
+(
+    "key"           VARCHAR(255) NOT NULL,
+    -- The command of the request, eg. create-claim or add-party/{claim_id}
+    scope           VARCHAR(255) NOT NULL,
+    request         JSONB NOT NULL,
+    response        JSONB,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY ("key", scope)
+);
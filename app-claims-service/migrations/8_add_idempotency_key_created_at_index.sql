@@ -0,0 +1,2 @@
+-- Expired keys are purged by their creation time
+CREATE INDEX IF NOT EXISTS idx_idempotency_key_created_at ON idempotency_key (created_at);
@@ -0,0 +1,19 @@
+use crate::common::error::AppError;
+use crate::service::claim_service::ClaimService;
+use async_graphql::async_trait::async_trait;
+use async_graphql::dataloader::Loader;
+use claims_model::model::Party;
+use std::collections::HashMap;
+
+/// Loads the parties of all the claims of a response in a single query
+pub struct PartiesLoader(pub ClaimService);
+
+#[async_trait]
+impl Loader<i32> for PartiesLoader {
+    type Value = Vec<Party>;
+    type Error = AppError;
+
+    async fn load(&self, claim_ids: &[i32]) -> Result<HashMap<i32, Vec<Party>>, AppError> {
+        self.0.fetch_parties_by_claims(claim_ids).await
+    }
+}
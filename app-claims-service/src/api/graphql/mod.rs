@@ -0,0 +1,112 @@
+use crate::api::graphql::loader::PartiesLoader;
+use crate::api::graphql::schema::{MutationRoot, QueryRoot};
+use crate::common::api::ApiContext;
+use crate::common::error::{match_error, AppError};
+use async_graphql::dataloader::DataLoader;
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{EmptySubscription, Error, ErrorExtensions, Schema, SchemaBuilder};
+use async_graphql_axum::GraphQL;
+use axum::response::{Html, IntoResponse};
+use axum::routing::get;
+use axum::Router;
+
+mod loader;
+mod schema;
+
+/// Deep enough for the introspection query of graphiql
+const MAX_DEPTH: usize = 16;
+/// Fields of a query (each field counts as 1)
+const MAX_COMPLEXITY: usize = 256;
+
+pub type ClaimsSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+fn builder() -> SchemaBuilder<QueryRoot, MutationRoot, EmptySubscription> {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+}
+
+/// Serves the graphql api at `/graphql` (POST) along with the graphiql ide (GET)
+pub fn routes(context: ApiContext) -> Router {
+    let parties = DataLoader::new(PartiesLoader(context.claims.clone()), tokio::spawn);
+    let schema: ClaimsSchema = builder().data(context).data(parties).finish();
+    Router::new().route("/graphql", get(graphiql).post_service(GraphQL::new(schema)))
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+impl ErrorExtensions for AppError {
+    // The message is the display of the error (the detail of the rest problems)
+    // along with the same code and invalid fields as extensions
+    fn extend(&self) -> Error {
+        let (code, _, _, _) = match_error(self);
+        Error::new(self.to_string()).extend_with(|_, e| {
+            if let Ok(code) = async_graphql::to_value(code) {
+                e.set("code", code);
+            }
+            if let AppError::Validation(fields) = self {
+                if let Ok(fields) = async_graphql::to_value(fields) {
+                    e.set("fields", fields);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_mirrors_the_rest_operations() {
+        let sdl = builder().finish().sdl();
+
+        for field in [
+            "claims(",
+            "claim(",
+            "party(",
+            "createClaim(",
+            "updateClaim(",
+            "addParty(",
+            "updateParty(",
+            "removeParty(",
+        ] {
+            assert!(sdl.contains(field), "missing {field} in\n{sdl}");
+        }
+        assert!(sdl.contains("union PartyData = Person | Vehicle"), "{sdl}");
+    }
+
+    async fn errors(query: String) -> Vec<String> {
+        let response = builder().finish().execute(query).await;
+        response.errors.into_iter().map(|e| e.message).collect()
+    }
+
+    #[tokio::test]
+    async fn rejects_queries_nested_too_deep() {
+        let nested = (0..MAX_DEPTH).fold("name".to_owned(), |q, _| format!("ofType {{ {q} }}"));
+        let query = format!("{{ __schema {{ types {{ fields {{ type {{ {nested} }} }} }} }} }}");
+
+        let errors = errors(query).await;
+
+        assert!(
+            errors.iter().any(|e| e.contains("nested too deep")),
+            "{errors:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_queries_too_complex() {
+        let fields: String = (0..MAX_COMPLEXITY)
+            .map(|i| format!("c{i}: claim(id: {i}) {{ id }} "))
+            .collect();
+
+        let errors = errors(format!("{{ {fields} }}")).await;
+
+        assert!(
+            errors.iter().any(|e| e.contains("too complex")),
+            "{errors:?}"
+        );
+    }
+}
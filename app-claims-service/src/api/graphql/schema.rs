@@ -0,0 +1,245 @@
+use crate::api::graphql::loader::PartiesLoader;
+use crate::common::api::ApiContext;
+use crate::common::error::AppError;
+use crate::db::claims;
+use crate::service::commands;
+use crate::service::queries::ListClaims;
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{Context, Enum, ErrorExtensions, Object, OneofObject, Result, SimpleObject};
+use claims_model::model::{Claim, ClaimStatus, IncidentType, Party, PartyData, Person, Vehicle};
+use std::future::Future;
+
+/// A claim, its parties are resolved on demand (in one batch for all the claims of a response)
+pub struct ClaimNode(Claim);
+
+#[Object(name = "Claim")]
+impl ClaimNode {
+    async fn id(&self) -> i32 {
+        self.0.id
+    }
+
+    async fn claim_no(&self) -> &str {
+        &self.0.claim_no
+    }
+
+    async fn status(&self) -> ClaimStatus {
+        self.0.status
+    }
+
+    async fn incident_type(&self) -> IncidentType {
+        self.0.incident_type
+    }
+
+    async fn version(&self) -> i32 {
+        self.0.version
+    }
+
+    async fn parties(&self, ctx: &Context<'_>) -> Result<Vec<Party>> {
+        let loader = ctx.data::<DataLoader<PartiesLoader>>()?;
+        let parties = extended(loader.load_one(self.0.id)).await?;
+        Ok(parties.unwrap_or_default())
+    }
+}
+
+/// Sort field of the claims listing
+#[derive(Enum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClaimSort {
+    #[default]
+    Id,
+    ClaimNo,
+    Status,
+    IncidentType,
+}
+
+impl From<ClaimSort> for claims::ClaimSort {
+    fn from(sort: ClaimSort) -> Self {
+        match sort {
+            ClaimSort::Id => claims::ClaimSort::Id,
+            ClaimSort::ClaimNo => claims::ClaimSort::ClaimNo,
+            ClaimSort::Status => claims::ClaimSort::Status,
+            ClaimSort::IncidentType => claims::ClaimSort::IncidentType,
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<SortOrder> for claims::SortOrder {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => claims::SortOrder::Asc,
+            SortOrder::Desc => claims::SortOrder::Desc,
+        }
+    }
+}
+
+/// A page of the claims listing along with the total number of matching claims
+#[derive(SimpleObject)]
+pub struct ClaimPage {
+    items: Vec<ClaimNode>,
+    offset: i64,
+    limit: i64,
+    total: i64,
+}
+
+/// Data of a party, either a person or a vehicle
+#[derive(OneofObject)]
+pub enum PartyDataInput {
+    Person(Person),
+    Vehicle(Vehicle),
+}
+
+impl From<PartyDataInput> for PartyData {
+    fn from(data: PartyDataInput) -> Self {
+        match data {
+            PartyDataInput::Person(person) => PartyData::Person(person),
+            PartyDataInput::Vehicle(vehicle) => PartyData::Vehicle(vehicle),
+        }
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A page of the claims, as the `GET /claims` listing
+    #[allow(clippy::too_many_arguments)]
+    async fn claims(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] offset: i64,
+        #[graphql(desc = "Defaults to 20, at most 100")] limit: Option<i64>,
+        status: Option<ClaimStatus>,
+        incident_type: Option<IncidentType>,
+        #[graphql(desc = "Prefix of the claim number")] claim_no: Option<String>,
+        #[graphql(default)] sort: ClaimSort,
+        #[graphql(default)] order: SortOrder,
+    ) -> Result<ClaimPage> {
+        let context = ctx.data::<ApiContext>()?;
+        let query = ListClaims {
+            offset,
+            limit: limit.unwrap_or(ListClaims::DEFAULT_LIMIT),
+            status,
+            incident_type,
+            claim_no,
+            sort: sort.into(),
+            order: order.into(),
+        };
+
+        let page = extended(context.claims.list_claims(query)).await?;
+
+        Ok(ClaimPage {
+            items: page.claims.into_iter().map(ClaimNode).collect(),
+            offset: page.offset,
+            limit: page.limit,
+            total: page.total,
+        })
+    }
+
+    async fn claim(&self, ctx: &Context<'_>, id: i32) -> Result<ClaimNode> {
+        let context = ctx.data::<ApiContext>()?;
+        let claim = extended(context.claims.fetch_claim(id)).await?;
+        Ok(ClaimNode(claim))
+    }
+
+    async fn party(&self, ctx: &Context<'_>, claim_id: i32, party_id: i32) -> Result<Party> {
+        let context = ctx.data::<ApiContext>()?;
+        extended(context.claims.fetch_party(claim_id, party_id)).await
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_claim(
+        &self,
+        ctx: &Context<'_>,
+        incident_type: IncidentType,
+    ) -> Result<ClaimNode> {
+        let context = ctx.data::<ApiContext>()?;
+        let claim = extended(context.claims.create_claim(commands::CreateClaim {
+            incident_type,
+            idempotency_key: None,
+        }))
+        .await?;
+        Ok(ClaimNode(claim))
+    }
+
+    /// Updates the claim, only if it is still at `version` when given
+    async fn update_claim(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        incident_type: IncidentType,
+        status: ClaimStatus,
+        version: Option<i32>,
+    ) -> Result<ClaimNode> {
+        let context = ctx.data::<ApiContext>()?;
+        let claim = extended(context.claims.update_claim(commands::UpdateClaim {
+            id,
+            incident_type,
+            status,
+            expected_version: version.into(),
+        }))
+        .await?;
+        Ok(ClaimNode(claim))
+    }
+
+    async fn add_party(
+        &self,
+        ctx: &Context<'_>,
+        claim_id: i32,
+        data: PartyDataInput,
+    ) -> Result<Party> {
+        let context = ctx.data::<ApiContext>()?;
+        extended(context.claims.add_party(commands::AddParty {
+            claim_id,
+            data: data.into(),
+            idempotency_key: None,
+        }))
+        .await
+    }
+
+    /// Updates the party, only if it is still at `version` when given
+    async fn update_party(
+        &self,
+        ctx: &Context<'_>,
+        claim_id: i32,
+        party_id: i32,
+        data: PartyDataInput,
+        version: Option<i32>,
+    ) -> Result<Party> {
+        let context = ctx.data::<ApiContext>()?;
+        extended(context.claims.update_party(commands::UpdateParty {
+            claim_id,
+            party_id,
+            data: data.into(),
+            expected_version: version.into(),
+        }))
+        .await
+    }
+
+    async fn remove_party(&self, ctx: &Context<'_>, claim_id: i32, party_id: i32) -> Result<Party> {
+        let context = ctx.data::<ApiContext>()?;
+        extended(
+            context
+                .claims
+                .remove_party(commands::RemoveParty { claim_id, party_id }),
+        )
+        .await
+    }
+}
+
+/// Resolves to the graphql error of the [`AppError`], see [`ErrorExtensions`] of [`AppError`]
+async fn extended<T, F>(f: F) -> Result<T>
+where
+    F: Future<Output = std::result::Result<T, AppError>>,
+{
+    f.await.map_err(|e| e.extend())
+}
@@ -0,0 +1,61 @@
+use bytes::{Buf, BufMut};
+use std::marker::PhantomData;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::Status;
+
+/// Codec of the rust-protobuf messages generated by claims-schema, tonic supports prost only
+pub struct ProtobufCodec<E, D>(PhantomData<(E, D)>);
+
+impl<E, D> Default for ProtobufCodec<E, D> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E, D> Codec for ProtobufCodec<E, D>
+where
+    E: protobuf::Message + Send + 'static,
+    D: protobuf::Message + Send + 'static,
+{
+    type Encode = E;
+    type Decode = D;
+    type Encoder = ProtobufEncoder<E>;
+    type Decoder = ProtobufDecoder<D>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        ProtobufEncoder(PhantomData)
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ProtobufDecoder(PhantomData)
+    }
+}
+
+pub struct ProtobufEncoder<T>(PhantomData<T>);
+
+impl<T: protobuf::Message> Encoder for ProtobufEncoder<T> {
+    type Item = T;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        let bytes = item
+            .write_to_bytes()
+            .map_err(|e| Status::internal(format!("Unable to encode message: {e}")))?;
+        dst.put_slice(&bytes);
+        Ok(())
+    }
+}
+
+pub struct ProtobufDecoder<T>(PhantomData<T>);
+
+impl<T: protobuf::Message> Decoder for ProtobufDecoder<T> {
+    type Item = T;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let bytes = src.copy_to_bytes(src.remaining());
+        T::parse_from_bytes(&bytes)
+            .map(Some)
+            .map_err(|e| Status::invalid_argument(format!("Unable to decode message: {e}")))
+    }
+}
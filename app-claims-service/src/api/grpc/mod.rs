@@ -0,0 +1,64 @@
+use crate::common::api::ApiContext;
+use crate::common::error::{match_error, AppError, ErrorCode};
+use claims_core::shutdown::ShutdownToken;
+use std::net::SocketAddr;
+use tonic::{Code, Status};
+
+pub mod codec;
+mod service;
+
+// Server and client of the ClaimsApi service, generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/claims.api.ClaimsApi.rs"));
+
+/// Starts the grpc server on `port` until shutdown
+pub async fn start_grpc_server(
+    context: ApiContext,
+    port: u16,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    tracing::info!("Grpc server listening on {addr}");
+    tonic::transport::Server::builder()
+        .add_service(claims_api_server::ClaimsApiServer::new(
+            service::ClaimsApiService::new(context),
+        ))
+        .serve_with_shutdown(addr, async move { shutdown.cancelled().await })
+        .await?;
+
+    Ok(())
+}
+
+impl From<AppError> for Status {
+    fn from(error: AppError) -> Self {
+        // Same error codes and details as the problems of the rest api
+        let (code, detail, log_message, _) = match_error(&error);
+        let status_code = match code {
+            ErrorCode::NotFound => Code::NotFound,
+            ErrorCode::MethodNotAllowed => Code::Unimplemented,
+            ErrorCode::VersionConflict => Code::Aborted,
+            ErrorCode::ValidationFailed
+            | ErrorCode::MalformedRequest
+            | ErrorCode::InvalidIdempotencyKey => Code::InvalidArgument,
+            ErrorCode::IdempotencyKeyReused => Code::FailedPrecondition,
+            ErrorCode::InternalError => Code::Internal,
+        };
+
+        if status_code == Code::Internal {
+            tracing::error!("{:?}", log_message);
+        } else if status_code == Code::NotFound {
+            tracing::warn!("{:?}", log_message);
+        }
+
+        let message = match &error {
+            AppError::Validation(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("{}: {}", f.field, f.message))
+                    .collect();
+                format!("{detail} ({})", fields.join(", "))
+            }
+            _ => detail,
+        };
+        Status::new(status_code, message)
+    }
+}
@@ -0,0 +1,279 @@
+use crate::api::grpc::claims_api_server::ClaimsApi;
+use crate::common::api::ApiContext;
+use crate::db::claims::{ClaimSort, SortOrder};
+use crate::service::commands;
+use crate::service::queries::ListClaims;
+use claims_core::correlation::CorrelationId;
+use claims_model::model::proto::claim::Claim as ClaimProto;
+use claims_model::model::proto::claims_api::{
+    self, AddPartyRequest, ClaimDetails, CreateClaimRequest, GetClaimRequest, GetPartyRequest,
+    ListClaimsRequest, ListClaimsResponse, ListPartiesRequest, ListPartiesResponse,
+    RemovePartyRequest, UpdateClaimRequest, UpdatePartyRequest,
+};
+use claims_model::model::proto::party::{Party as PartyProto, PartyData as PartyDataProto};
+use claims_model::model::proto::ProtoMap;
+use claims_model::model::{ClaimStatus, IncidentType, PartyData};
+use protobuf::{EnumOrUnknown, MessageField};
+use std::future::Future;
+use tonic::{Request, Response, Status};
+
+/// The claims grpc api, a thin layer over the claim service as the rest endpoints
+pub struct ClaimsApiService {
+    context: ApiContext,
+}
+
+impl ClaimsApiService {
+    pub fn new(context: ApiContext) -> Self {
+        Self { context }
+    }
+}
+
+#[tonic::async_trait]
+impl ClaimsApi for ClaimsApiService {
+    async fn list_claims(
+        &self,
+        request: Request<ListClaimsRequest>,
+    ) -> Result<Response<ListClaimsResponse>, Status> {
+        scoped(request, |request| async move {
+            let query = ListClaims {
+                offset: request.offset,
+                limit: request.limit.unwrap_or(ListClaims::DEFAULT_LIMIT),
+                status: request
+                    .status
+                    .map(|status| enumeration(status, "status").and_then(from_proto))
+                    .transpose()?,
+                incident_type: request
+                    .incident_type
+                    .map(|t| enumeration(t, "incident_type").and_then(from_proto))
+                    .transpose()?,
+                claim_no: request.claim_no,
+                sort: claim_sort(enumeration(request.sort, "sort")?),
+                order: sort_order(enumeration(request.order, "order")?),
+            };
+
+            let page = self.context.claims.list_claims(query).await?;
+
+            Ok(ListClaimsResponse {
+                claims: page.claims.iter().map(|c| c.to_proto()).collect(),
+                offset: page.offset,
+                limit: page.limit,
+                total: page.total,
+                ..Default::default()
+            })
+        })
+        .await
+    }
+
+    async fn get_claim(
+        &self,
+        request: Request<GetClaimRequest>,
+    ) -> Result<Response<ClaimDetails>, Status> {
+        scoped(request, |request| async move {
+            let (claim, parties) = self.context.claims.fetch_details(request.id).await?;
+            Ok(ClaimDetails {
+                claim: MessageField::some(claim.to_proto()),
+                parties: parties.iter().map(|p| p.to_proto()).collect(),
+                ..Default::default()
+            })
+        })
+        .await
+    }
+
+    async fn create_claim(
+        &self,
+        request: Request<CreateClaimRequest>,
+    ) -> Result<Response<ClaimProto>, Status> {
+        scoped(request, |request| async move {
+            let incident_type: IncidentType =
+                from_proto(enumeration(request.incident_type, "incident_type")?)?;
+
+            let claim = self
+                .context
+                .claims
+                .create_claim(commands::CreateClaim {
+                    incident_type,
+                    idempotency_key: None,
+                })
+                .await?;
+
+            Ok(claim.to_proto())
+        })
+        .await
+    }
+
+    async fn update_claim(
+        &self,
+        request: Request<UpdateClaimRequest>,
+    ) -> Result<Response<ClaimProto>, Status> {
+        scoped(request, |request| async move {
+            let incident_type: IncidentType =
+                from_proto(enumeration(request.incident_type, "incident_type")?)?;
+            let status: ClaimStatus = from_proto(enumeration(request.status, "status")?)?;
+
+            let claim = self
+                .context
+                .claims
+                .update_claim(commands::UpdateClaim {
+                    id: request.id,
+                    incident_type,
+                    status,
+                    expected_version: request.version.into(),
+                })
+                .await?;
+
+            Ok(claim.to_proto())
+        })
+        .await
+    }
+
+    async fn list_parties(
+        &self,
+        request: Request<ListPartiesRequest>,
+    ) -> Result<Response<ListPartiesResponse>, Status> {
+        scoped(request, |request| async move {
+            let parties = self.context.claims.fetch_parties(request.claim_id).await?;
+            Ok(ListPartiesResponse {
+                parties: parties.iter().map(|p| p.to_proto()).collect(),
+                ..Default::default()
+            })
+        })
+        .await
+    }
+
+    async fn get_party(
+        &self,
+        request: Request<GetPartyRequest>,
+    ) -> Result<Response<PartyProto>, Status> {
+        scoped(request, |request| async move {
+            let party = self
+                .context
+                .claims
+                .fetch_party(request.claim_id, request.party_id)
+                .await?;
+            Ok(party.to_proto())
+        })
+        .await
+    }
+
+    async fn add_party(
+        &self,
+        request: Request<AddPartyRequest>,
+    ) -> Result<Response<PartyProto>, Status> {
+        scoped(request, |request| async move {
+            let party = self
+                .context
+                .claims
+                .add_party(commands::AddParty {
+                    claim_id: request.claim_id,
+                    data: party_data(request.data)?,
+                    idempotency_key: None,
+                })
+                .await?;
+
+            Ok(party.to_proto())
+        })
+        .await
+    }
+
+    async fn update_party(
+        &self,
+        request: Request<UpdatePartyRequest>,
+    ) -> Result<Response<PartyProto>, Status> {
+        scoped(request, |request| async move {
+            let party = self
+                .context
+                .claims
+                .update_party(commands::UpdateParty {
+                    claim_id: request.claim_id,
+                    party_id: request.party_id,
+                    data: party_data(request.data)?,
+                    expected_version: request.version.into(),
+                })
+                .await?;
+
+            Ok(party.to_proto())
+        })
+        .await
+    }
+
+    async fn remove_party(
+        &self,
+        request: Request<RemovePartyRequest>,
+    ) -> Result<Response<PartyProto>, Status> {
+        scoped(request, |request| async move {
+            let party = self
+                .context
+                .claims
+                .remove_party(commands::RemoveParty {
+                    claim_id: request.claim_id,
+                    party_id: request.party_id,
+                })
+                .await?;
+
+            Ok(party.to_proto())
+        })
+        .await
+    }
+}
+
+/// Handles the message of the request with the `x-correlation-id` of its metadata
+/// (or a new one) as the [`CorrelationId::current`] id, as the rest api does
+async fn scoped<Req, Res, F, Fut>(request: Request<Req>, handle: F) -> Result<Response<Res>, Status>
+where
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<Res, Status>>,
+{
+    let correlation_id = request
+        .metadata()
+        .get(CorrelationId::HTTP_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(CorrelationId::parse)
+        .unwrap_or_else(CorrelationId::generate);
+
+    let mut response = correlation_id
+        .clone()
+        .scope(handle(request.into_inner()))
+        .await
+        .map(Response::new)?;
+    if let Ok(value) = correlation_id.as_str().parse() {
+        response
+            .metadata_mut()
+            .insert(CorrelationId::HTTP_HEADER, value);
+    }
+    Ok(response)
+}
+
+/// The known value of an enum field
+fn enumeration<E: protobuf::Enum>(value: EnumOrUnknown<E>, field: &str) -> Result<E, Status> {
+    value
+        .enum_value()
+        .map_err(|v| Status::invalid_argument(format!("{field}: unknown value {v}")))
+}
+
+fn claim_sort(sort: claims_api::ClaimSort) -> ClaimSort {
+    match sort {
+        claims_api::ClaimSort::ID => ClaimSort::Id,
+        claims_api::ClaimSort::CLAIM_NO => ClaimSort::ClaimNo,
+        claims_api::ClaimSort::STATUS => ClaimSort::Status,
+        claims_api::ClaimSort::INCIDENT_TYPE => ClaimSort::IncidentType,
+    }
+}
+
+fn sort_order(order: claims_api::SortOrder) -> SortOrder {
+    match order {
+        claims_api::SortOrder::ASC => SortOrder::Asc,
+        claims_api::SortOrder::DESC => SortOrder::Desc,
+    }
+}
+
+fn from_proto<T: ProtoMap>(proto: T::ProtoStruct) -> Result<T, Status> {
+    T::from_proto(proto).map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
+/// The required party data of a request
+fn party_data(data: MessageField<PartyDataProto>) -> Result<PartyData, Status> {
+    let data = data
+        .into_option()
+        .ok_or_else(|| Status::invalid_argument("data: is required"))?;
+    from_proto(data)
+}
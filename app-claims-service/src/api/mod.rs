@@ -1 +1,3 @@
-pub mod rest;
\ No newline at end of file
+pub mod graphql;
+pub mod grpc;
+pub mod rest;
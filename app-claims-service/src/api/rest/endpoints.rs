@@ -1,144 +1,235 @@
-use crate::api::rest::resources::{CreateClaim, CreateParty, UpdateClaim, UpdateParty};
+use crate::api::rest::etag::{IfMatch, Tagged};
+use crate::api::rest::extract::{Extension, JsonBody, Path, Query};
+use crate::api::rest::idempotency::IdempotencyKey;
+use crate::api::rest::resources::{
+    ClaimDetails, ClaimsQuery, CreateClaim, CreateParty, Page, UpdateClaim, UpdateParty,
+};
 use crate::common::api::ApiContext;
 use crate::common::error::AppError;
-use crate::common::error::DbError::NotFound;
-use crate::db::entities::{ClaimDb, PartyDb};
-use crate::{common, db};
-use axum::extract::Path;
-use axum::{Extension, Json};
+use crate::service::commands;
+use axum::Json;
 use claims_model::model::{Claim, Party};
-// TODO add proper validation to all update endpoints
-// TODO refactor endpoint internals to be reusable for other apis eg. grpc, graphql etc...
-// TODO update_party should not change the type of a party, only the subtype validation
 
+#[utoipa::path(
+    get,
+    path = "/claims",
+    tag = "claims",
+    params(ClaimsQuery),
+    responses(
+        (status = 200, description = "A page of the matching claims", body = crate::api::rest::resources::ClaimPage),
+        (status = 400, description = "Invalid query", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn fetch_all_claims(
     Extension(context): Extension<ApiContext>,
-) -> Result<Json<Vec<Claim>>, AppError> {
-    let entities = db::claims::fetch_all(&context.db).await?;
-    let claims: Vec<Claim> = entities.into_iter().map(|e| e.into()).collect();
-    Ok(claims.into())
+    Query(query): Query<ClaimsQuery>,
+) -> Result<Json<Page<Claim>>, AppError> {
+    let page = context.claims.list_claims(query.into()).await?;
+    Ok(Json(page.into()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/claims/{id}",
+    tag = "claims",
+    params(("id" = i32, Path, description = "Claim id")),
+    responses(
+        (status = 200, description = "The claim with its parties", body = ClaimDetails, headers(("ETag" = String, description = "Version of the resource, for If-Match"))),
+        (status = 404, description = "Claim not found", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn fetch_claim(
+    Extension(context): Extension<ApiContext>,
+    Path(id): Path<i32>,
+) -> Result<Tagged<ClaimDetails>, AppError> {
+    let (claim, parties) = context.claims.fetch_details(id).await?;
+    Ok(Tagged(ClaimDetails { claim, parties }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/claims/{id}/parties",
+    tag = "parties",
+    params(("id" = i32, Path, description = "Claim id")),
+    responses(
+        (status = 200, description = "The parties of the claim", body = [Party]),
+        (status = 404, description = "Claim not found", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn fetch_parties(
+    Extension(context): Extension<ApiContext>,
+    Path(claim_id): Path<i32>,
+) -> Result<Json<Vec<Party>>, AppError> {
+    let parties = context.claims.fetch_parties(claim_id).await?;
+    Ok(parties.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/claims/{id}/parties/{party_id}",
+    tag = "parties",
+    params(("id" = i32, Path, description = "Claim id"), ("party_id" = i32, Path, description = "Party id")),
+    responses(
+        (status = 200, description = "The party", body = Party, headers(("ETag" = String, description = "Version of the resource, for If-Match"))),
+        (status = 404, description = "Party not found", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn fetch_party(
+    Extension(context): Extension<ApiContext>,
+    Path((claim_id, party_id)): Path<(i32, i32)>,
+) -> Result<Tagged<Party>, AppError> {
+    let party = context.claims.fetch_party(claim_id, party_id).await?;
+    Ok(Tagged(party))
+}
+
+#[utoipa::path(
+    post,
+    path = "/claims/{id}",
+    tag = "claims",
+    params(("id" = i32, Path, description = "Claim id"), ("If-Match" = Option<String>, Header, description = "ETag of the version being updated")),
+    request_body = UpdateClaim,
+    responses(
+        (status = 200, description = "The updated claim", body = Claim, headers(("ETag" = String, description = "Version of the resource, for If-Match"))),
+        (status = 404, description = "Claim not found", body = crate::common::error::Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The claim is not at the version of If-Match or was modified concurrently", body = crate::common::error::Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid claim", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn update_claim(
     Extension(context): Extension<ApiContext>,
     Path(id): Path<i32>,
-    Json(update_claim): Json<UpdateClaim>,
-) -> Result<Json<Claim>, AppError> {
+    if_match: IfMatch,
+    JsonBody(update_claim): JsonBody<UpdateClaim>,
+) -> Result<Tagged<Claim>, AppError> {
     let UpdateClaim {
         incident_type,
         status,
     } = update_claim;
 
-    let mut tx = context.db.begin().await?;
-    let mut e = db::claims::fetch_one(&mut *tx, id)
-        .await?
-        .ok_or(AppError::DbError(NotFound))?;
-
-    e.status = status;
-    e.incident_type = incident_type;
-
-    let entity = db::claims::update(&mut tx, e).await?;
-
-    let claim: Claim = entity.into();
-    context.events.send_claim(&mut tx, &claim).await?;
-
-    tx.commit().await?;
-
-    Ok(claim.into())
+    let claim = context
+        .claims
+        .update_claim(commands::UpdateClaim {
+            id,
+            incident_type,
+            status,
+            expected_version: if_match.into(),
+        })
+        .await?;
+
+    Ok(Tagged(claim))
 }
 
+#[utoipa::path(
+    post,
+    path = "/claims",
+    tag = "claims",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to safely retry the request")),
+    request_body = CreateClaim,
+    responses(
+        (status = 200, description = "The created claim", body = Claim, headers(("ETag" = String, description = "Version of the resource, for If-Match"))),
+        (status = 422, description = "Invalid claim or idempotency key reused", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn create_claim(
     Extension(context): Extension<ApiContext>,
-    Json(create_claim): Json<CreateClaim>,
-) -> Result<Json<Claim>, AppError> {
-    let CreateClaim { incident_type } = create_claim;
-    let claim_no = common::misc::generate_random_string(10);
-
-    // Create a new entity via a new transaction
-    let mut tx = context.db.begin().await?;
-    let entity = ClaimDb::new(claim_no, incident_type);
-    let entity = db::claims::create(&mut tx, entity).await?;
-
-    let claim: Claim = entity.into();
-    context.events.send_claim(&mut tx, &claim).await?;
-
-    tx.commit().await?;
+    idempotency_key: IdempotencyKey,
+    JsonBody(create_claim): JsonBody<CreateClaim>,
+) -> Result<Tagged<Claim>, AppError> {
+    let claim = context
+        .claims
+        .create_claim(commands::CreateClaim {
+            incident_type: create_claim.incident_type,
+            idempotency_key: idempotency_key.0,
+        })
+        .await?;
 
     // Return the new created claim
-
-    Ok(claim.into())
+    Ok(Tagged(claim))
 }
 
+#[utoipa::path(
+    post,
+    path = "/claims/{id}/parties",
+    tag = "parties",
+    params(("id" = i32, Path, description = "Claim id"), ("Idempotency-Key" = Option<String>, Header, description = "Key to safely retry the request")),
+    request_body = CreateParty,
+    responses(
+        (status = 200, description = "The created party", body = Party, headers(("ETag" = String, description = "Version of the resource, for If-Match"))),
+        (status = 404, description = "Claim not found", body = crate::common::error::Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid party or idempotency key reused", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn add_party(
     Extension(context): Extension<ApiContext>,
     Path(claim_id): Path<i32>,
-    Json(create_party): Json<CreateParty>,
-) -> Result<Json<Party>, AppError> {
+    idempotency_key: IdempotencyKey,
+    JsonBody(create_party): JsonBody<CreateParty>,
+) -> Result<Tagged<Party>, AppError> {
     let CreateParty { data } = create_party;
-
-    let mut tx = context.db.begin().await?;
-    let claim = db::claims::fetch_one(&context.db, claim_id)
-        .await?
-        .ok_or(AppError::DbError(NotFound))?;
-    let party = PartyDb::new(claim.id, data);
-    let party = db::parties::create(&mut tx, party).await?;
-
-    let party: Party = party.into();
-    context.events.send_party(&mut tx, &party).await?;
-
-    tx.commit().await?;
-
-    Ok(party.into())
+    let party = context
+        .claims
+        .add_party(commands::AddParty {
+            claim_id,
+            data,
+            idempotency_key: idempotency_key.0,
+        })
+        .await?;
+
+    Ok(Tagged(party))
 }
 
+#[utoipa::path(
+    post,
+    path = "/claims/{id}/parties/{party_id}",
+    tag = "parties",
+    params(("id" = i32, Path, description = "Claim id"), ("party_id" = i32, Path, description = "Party id"), ("If-Match" = Option<String>, Header, description = "ETag of the version being updated")),
+    request_body = UpdateParty,
+    responses(
+        (status = 200, description = "The updated party", body = Party, headers(("ETag" = String, description = "Version of the resource, for If-Match"))),
+        (status = 404, description = "Party not found", body = crate::common::error::Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The party is not at the version of If-Match or was modified concurrently", body = crate::common::error::Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid party", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn update_party(
     Extension(context): Extension<ApiContext>,
     Path((claim_id, party_id)): Path<(i32, i32)>,
-    Json(update_party): Json<UpdateParty>,
-) -> Result<Json<Party>, AppError> {
+    if_match: IfMatch,
+    JsonBody(update_party): JsonBody<UpdateParty>,
+) -> Result<Tagged<Party>, AppError> {
     let UpdateParty { data } = update_party;
-    let mut tx = context.db.begin().await?;
-    // Validate that the claim exists
-    db::claims::fetch_one(&context.db, claim_id)
-        .await?
-        .ok_or(AppError::DbError(NotFound))?;
-
-    let mut party = db::parties::fetch_one(&context.db, party_id)
-        .await?
-        .ok_or(AppError::DbError(NotFound))?;
-
-    party.r#type = data.r#type();
-    party.subtype = data.subtype();
-    party.data = sqlx::types::Json(data);
-
-    let party = db::parties::update(&mut tx, party).await?;
-
-    let party: Party = party.into();
-    context.events.send_party(&mut tx, &party).await?;
 
-    tx.commit().await?;
-
-    Ok(party.into())
+    let party = context
+        .claims
+        .update_party(commands::UpdateParty {
+            claim_id,
+            party_id,
+            data,
+            expected_version: if_match.into(),
+        })
+        .await?;
+
+    Ok(Tagged(party))
 }
 
-// TODO send message party delete (outbox)
+#[utoipa::path(
+    delete,
+    path = "/claims/{id}/parties/{party_id}",
+    tag = "parties",
+    params(("id" = i32, Path, description = "Claim id"), ("party_id" = i32, Path, description = "Party id")),
+    responses(
+        (status = 200, description = "The removed party", body = Party),
+        (status = 404, description = "Party not found", body = crate::common::error::Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn remove_party(
     Extension(context): Extension<ApiContext>,
     Path((claim_id, party_id)): Path<(i32, i32)>,
 ) -> Result<Json<Party>, AppError> {
-    let mut tx = context.db.begin().await?;
-    // Validate that the claim exists
-    db::claims::fetch_one(&context.db, claim_id)
-        .await?
-        .ok_or(AppError::DbError(NotFound))?;
-
-    let party = db::parties::fetch_one(&context.db, party_id)
-        .await?
-        .ok_or(AppError::DbError(NotFound))?;
-    let party = db::parties::delete(&mut tx, party).await?;
-
-    tx.commit().await?;
+    let party = context
+        .claims
+        .remove_party(commands::RemoveParty { claim_id, party_id })
+        .await?;
 
-    let party: Party = party.into();
     Ok(party.into())
 }
@@ -0,0 +1,133 @@
+use crate::api::rest::resources::ClaimDetails;
+use crate::common::error::AppError;
+use crate::service::commands::ExpectedVersion;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::{ETAG, IF_MATCH};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use claims_model::model::{Claim, Party};
+use serde::Serialize;
+
+/// Resources carrying the row version used for optimistic concurrency
+pub trait Versioned {
+    fn version(&self) -> i32;
+}
+
+impl Versioned for Claim {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for Party {
+    fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Versioned for ClaimDetails {
+    fn version(&self) -> i32 {
+        self.claim.version
+    }
+}
+
+/// Json response with the version of the resource as its `ETag` header
+pub struct Tagged<T>(pub T);
+
+impl<T: Serialize + Versioned> IntoResponse for Tagged<T> {
+    fn into_response(self) -> Response {
+        let etag = format!("\"{}\"", self.0.version());
+        ([(ETAG, etag)], Json(self.0)).into_response()
+    }
+}
+
+/// The `If-Match` precondition of an update, no header matches any version
+#[derive(Debug, PartialEq, Eq)]
+pub enum IfMatch {
+    Any,
+    Versions(Vec<i32>),
+}
+
+impl IfMatch {
+    /// Parses `*` or a list of entity tags such as `"3", "4"`.
+    ///
+    /// `If-Match` uses the strong comparison (RFC 9110), so weak tags such as `W/"3"` are rejected
+    /// and never match.
+    fn parse(value: &str) -> Self {
+        if value.trim() == "*" {
+            return IfMatch::Any;
+        }
+        let versions = value
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.starts_with("W/"))
+            // Tags that are not versions of ours never match
+            .filter_map(|tag| tag.trim_matches('"').parse().ok())
+            .collect();
+        IfMatch::Versions(versions)
+    }
+}
+
+impl From<IfMatch> for ExpectedVersion {
+    fn from(if_match: IfMatch) -> Self {
+        match if_match {
+            IfMatch::Any => ExpectedVersion::Any,
+            IfMatch::Versions(versions) => ExpectedVersion::OneOf(versions),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(IF_MATCH) {
+            None => Ok(IfMatch::Any),
+            Some(value) => Ok(value
+                .to_str()
+                .map(IfMatch::parse)
+                .unwrap_or(IfMatch::Versions(vec![]))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IfMatch;
+    use crate::common::error::ErrorCode;
+    use crate::service::commands::ExpectedVersion;
+
+    #[test]
+    fn parses_if_match() {
+        assert_eq!(IfMatch::parse("*"), IfMatch::Any);
+        assert_eq!(IfMatch::parse(" * "), IfMatch::Any);
+        assert_eq!(IfMatch::parse(r#""3""#), IfMatch::Versions(vec![3]));
+        assert_eq!(IfMatch::parse("3"), IfMatch::Versions(vec![3]));
+        assert_eq!(
+            IfMatch::parse(r#""3", "4",5"#),
+            IfMatch::Versions(vec![3, 4, 5])
+        );
+        // Tags that are not versions never match
+        assert_eq!(IfMatch::parse(r#""abc""#), IfMatch::Versions(vec![]));
+    }
+
+    #[test]
+    fn weak_tags_never_match() {
+        assert_eq!(IfMatch::parse(r#"W/"3""#), IfMatch::Versions(vec![]));
+        assert_eq!(IfMatch::parse(r#"W/"3", "4""#), IfMatch::Versions(vec![4]));
+    }
+
+    #[test]
+    fn weak_tag_of_the_current_version_conflicts() {
+        let expected = ExpectedVersion::from(IfMatch::parse(r#"W/"3""#));
+
+        let problem = expected.check(3).unwrap_err().problem();
+
+        assert_eq!(problem.status, 409);
+        assert_eq!(problem.code, ErrorCode::VersionConflict);
+        assert_eq!(problem.r#type, "/problems/version-conflict");
+    }
+}
@@ -0,0 +1,28 @@
+use crate::common::error::{AppError, IdempotencyError};
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+
+const HEADER: &str = "idempotency-key";
+const MAX_LEN: usize = 255;
+
+/// The optional `Idempotency-Key` header of a create request, the idempotency key of its command
+/// (see [`crate::service::commands::CreateClaim`])
+pub struct IdempotencyKey(pub Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(HEADER) else {
+            return Ok(IdempotencyKey(None));
+        };
+        match value.to_str().map(str::trim) {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_LEN => {
+                Ok(IdempotencyKey(Some(key.to_owned())))
+            }
+            _ => Err(AppError::Idempotency(IdempotencyError::InvalidKey)),
+        }
+    }
+}
@@ -1,3 +1,7 @@
 pub mod routing;
 pub mod endpoints;
-mod resources;
\ No newline at end of file
+mod etag;
+pub mod extract;
+mod idempotency;
+pub mod openapi;
+pub mod resources;
\ No newline at end of file
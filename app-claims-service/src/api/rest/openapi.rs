@@ -0,0 +1,179 @@
+use crate::api::rest::endpoints;
+use crate::api::rest::resources::{
+    ClaimDetails, ClaimPage, ClaimSort, CreateClaim, CreateParty, SortOrder, UpdateClaim,
+    UpdateParty,
+};
+use crate::common::error::{ErrorCode, FieldError, Problem};
+use axum::Router;
+use claims_model::model::{
+    Claim, ClaimStatus, IncidentType, Party, PartyData, PartySubtype, PartyType, Person, Vehicle,
+};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// OpenAPI document of the claims REST api
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Claims service"),
+    paths(
+        endpoints::fetch_all_claims,
+        endpoints::create_claim,
+        endpoints::fetch_claim,
+        endpoints::update_claim,
+        endpoints::fetch_parties,
+        endpoints::add_party,
+        endpoints::fetch_party,
+        endpoints::update_party,
+        endpoints::remove_party,
+    ),
+    components(schemas(
+        Claim,
+        ClaimStatus,
+        IncidentType,
+        Party,
+        PartyType,
+        PartySubtype,
+        PartyData,
+        Person,
+        Vehicle,
+        ClaimDetails,
+        ClaimPage,
+        ClaimSort,
+        SortOrder,
+        CreateClaim,
+        UpdateClaim,
+        CreateParty,
+        UpdateParty,
+        Problem,
+        FieldError,
+        ErrorCode,
+    )),
+    tags(
+        (name = "claims", description = "Claims"),
+        (name = "parties", description = "Parties involved in a claim"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Serves the document at `/openapi.json` along with the swagger ui at `/swagger-ui`
+pub fn routes() -> Router {
+    SwaggerUi::new("/swagger-ui")
+        .url("/openapi.json", ApiDoc::openapi())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::routing;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use tower::ServiceExt;
+    use utoipa::openapi::PathItemType;
+
+    fn method(item: &PathItemType) -> Method {
+        match item {
+            PathItemType::Get => Method::GET,
+            PathItemType::Post => Method::POST,
+            PathItemType::Put => Method::PUT,
+            PathItemType::Delete => Method::DELETE,
+            PathItemType::Options => Method::OPTIONS,
+            PathItemType::Head => Method::HEAD,
+            PathItemType::Patch => Method::PATCH,
+            PathItemType::Trace => Method::TRACE,
+            PathItemType::Connect => Method::CONNECT,
+        }
+    }
+
+    /// Every documented operation is routed and every routed method of a documented path is documented
+    #[tokio::test]
+    async fn spec_matches_the_routes() {
+        let spec = ApiDoc::openapi();
+        assert!(!spec.paths.paths.is_empty());
+
+        for (path, item) in &spec.paths.paths {
+            let uri = path.replace("{id}", "1").replace("{party_id}", "2");
+            let documented: Vec<Method> = item.operations.keys().map(method).collect();
+
+            for probe in [
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ] {
+                let request = Request::builder()
+                    .method(probe.clone())
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap();
+                // Routed handlers fail extracting the missing api context instead
+                let status = routing::init().oneshot(request).await.unwrap().status();
+                let routed =
+                    status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED;
+
+                assert_eq!(
+                    routed,
+                    documented.contains(&probe),
+                    "{probe} {path} routed: {routed}, documented: {}",
+                    !routed
+                );
+            }
+        }
+    }
+
+    /// The generated document is the checked-in `openapi.json`, so that every change of the api
+    /// shows in review. Run with `UPDATE_OPENAPI_SNAPSHOT=1` to accept the changes.
+    #[test]
+    fn spec_matches_the_snapshot() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+        let generated = ApiDoc::openapi().to_pretty_json().unwrap();
+        if std::env::var_os("UPDATE_OPENAPI_SNAPSHOT").is_some() {
+            std::fs::write(path, format!("{generated}\n")).unwrap();
+        }
+
+        let snapshot = std::fs::read_to_string(path).unwrap_or_else(|_| {
+            panic!("Missing {path}, run with UPDATE_OPENAPI_SNAPSHOT=1 to create it")
+        });
+        let generated: serde_json::Value = serde_json::from_str(&generated).unwrap();
+        let snapshot: serde_json::Value = serde_json::from_str(&snapshot).unwrap();
+        assert!(
+            generated == snapshot,
+            "The OpenAPI document differs from {path}, \
+            run with UPDATE_OPENAPI_SNAPSHOT=1 to update it and review the diff"
+        );
+    }
+
+    /// Schemas referenced by the operations are registered as components
+    #[test]
+    fn spec_references_resolve() {
+        fn collect_refs(value: &serde_json::Value, refs: &mut Vec<String>) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    for (key, value) in map {
+                        match (key.as_str(), value) {
+                            ("$ref", serde_json::Value::String(r)) => refs.push(r.clone()),
+                            _ => collect_refs(value, refs),
+                        }
+                    }
+                }
+                serde_json::Value::Array(values) => {
+                    values.iter().for_each(|v| collect_refs(v, refs))
+                }
+                _ => {}
+            }
+        }
+
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let mut refs = vec![];
+        collect_refs(&spec, &mut refs);
+
+        for r in refs {
+            let name = r.trim_start_matches("#/components/schemas/");
+            assert!(
+                spec["components"]["schemas"].get(name).is_some(),
+                "{r} is not a registered schema"
+            );
+        }
+    }
+}
@@ -1,26 +1,147 @@
-use claims_model::model::{ClaimStatus, IncidentType, PartyData};
+use crate::db::claims;
+use crate::service::queries::{ClaimsPage, ListClaims};
+use claims_model::model::{Claim, ClaimStatus, IncidentType, Party, PartyData};
 use serde::{Deserialize, Serialize};
-#[derive(Serialize, Deserialize)]
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateClaim {
     pub incident_type: IncidentType,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateClaim {
     pub incident_type: IncidentType,
     pub status: ClaimStatus,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateParty {
     pub data: PartyData,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateParty {
     pub data: PartyData,
 }
+
+/// A claim along with its parties
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimDetails {
+    #[serde(flatten)]
+    pub claim: Claim,
+    pub parties: Vec<Party>,
+}
+
+/// Sort field of the claims listing
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ClaimSort {
+    #[default]
+    Id,
+    ClaimNo,
+    Status,
+    IncidentType,
+}
+
+impl From<ClaimSort> for claims::ClaimSort {
+    fn from(sort: ClaimSort) -> Self {
+        match sort {
+            ClaimSort::Id => claims::ClaimSort::Id,
+            ClaimSort::ClaimNo => claims::ClaimSort::ClaimNo,
+            ClaimSort::Status => claims::ClaimSort::Status,
+            ClaimSort::IncidentType => claims::ClaimSort::IncidentType,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<SortOrder> for claims::SortOrder {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => claims::SortOrder::Asc,
+            SortOrder::Desc => claims::SortOrder::Desc,
+        }
+    }
+}
+
+/// Query of the claims listing, eg. `/claims?status=OPEN&claimNo=AB&sort=claimNo&order=desc&offset=20&limit=10`
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ClaimsQuery {
+    /// Number of claims to skip
+    #[serde(default)]
+    #[param(default = 0, minimum = 0)]
+    pub offset: i64,
+    /// Number of claims of the page
+    #[serde(default = "ClaimsQuery::default_limit")]
+    #[param(default = 20, minimum = 1, maximum = 100)]
+    pub limit: i64,
+    #[param(inline)]
+    pub status: Option<ClaimStatus>,
+    #[param(inline)]
+    pub incident_type: Option<IncidentType>,
+    /// Prefix of the claim number
+    pub claim_no: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    pub sort: ClaimSort,
+    #[serde(default)]
+    #[param(inline)]
+    pub order: SortOrder,
+}
+
+impl ClaimsQuery {
+    fn default_limit() -> i64 {
+        ListClaims::DEFAULT_LIMIT
+    }
+}
+
+impl From<ClaimsQuery> for ListClaims {
+    fn from(query: ClaimsQuery) -> Self {
+        Self {
+            offset: query.offset,
+            limit: query.limit,
+            status: query.status,
+            incident_type: query.incident_type,
+            claim_no: query.claim_no,
+            sort: query.sort.into(),
+            order: query.order.into(),
+        }
+    }
+}
+
+/// A page of a listing along with the total number of matching items
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[aliases(ClaimPage = Page<Claim>)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub offset: i64,
+    pub limit: i64,
+    pub total: i64,
+}
+
+impl From<ClaimsPage> for Page<Claim> {
+    fn from(page: ClaimsPage) -> Self {
+        Self {
+            items: page.claims,
+            offset: page.offset,
+            limit: page.limit,
+            total: page.total,
+        }
+    }
+}
@@ -1,7 +1,6 @@
 use crate::config::AppConfig;
-use crate::service::event_service::EventService;
-use axum::response::IntoResponse;
-use serde_json::json;
+use crate::service::claim_service::ClaimService;
+use claims_core::metrics::PrometheusHandle;
 use sqlx::PgPool;
 use std::sync::Arc;
 
@@ -9,9 +8,6 @@ use std::sync::Arc;
 pub struct ApiContext {
     pub config: Arc<AppConfig>,
     pub db: PgPool,
-    pub events: EventService,
-}
-
-pub async fn health() -> impl IntoResponse {
-    axum::Json(json!({ "status" : "UP" }))
+    pub claims: ClaimService,
+    pub metrics: PrometheusHandle,
 }
@@ -0,0 +1,27 @@
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use claims_core::correlation::CorrelationId;
+
+/// Middleware that accepts the `X-Correlation-Id` of the request or generates a new one.
+///
+/// The id is echoed in the response, inserted in the request extensions and is the
+/// [`CorrelationId::current`] id while the request is handled (eg. stored with the outbox events).
+pub async fn correlation_id<B>(mut req: Request<B>, next: Next<B>) -> Response {
+    let correlation_id = req
+        .headers()
+        .get(CorrelationId::HTTP_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(CorrelationId::parse)
+        .unwrap_or_else(CorrelationId::generate);
+
+    req.extensions_mut().insert(correlation_id.clone());
+    let mut response = correlation_id.clone().scope(next.run(req)).await;
+
+    if let Ok(value) = HeaderValue::from_str(correlation_id.as_str()) {
+        response
+            .headers_mut()
+            .insert(CorrelationId::HTTP_HEADER, value);
+    }
+    response
+}
@@ -1,13 +1,33 @@
-use axum::http::StatusCode;
+use axum::extract::rejection::{ExtensionRejection, JsonRejection, PathRejection, QueryRejection};
+use axum::http::header::{ALLOW, CONTENT_TYPE};
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
-use axum::Json;
-use serde_json::json;
+use axum::{middleware, Router};
+use serde::Serialize;
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
+use utoipa::ToSchema;
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+tokio::task_local! {
+    /// Path of the request being handled, the `instance` of its problems
+    static INSTANCE: String;
+}
 
 #[derive(Clone, Debug)]
 pub enum AppError {
     DbError(DbError),
+    Idempotency(IdempotencyError),
+    /// Invalid fields of the request
+    Validation(Vec<FieldError>),
+    /// A request that could not be extracted (eg. malformed json body or path)
+    Rejection(StatusCode, String),
+    /// No route matches the path of the request
+    NoRoute,
+    /// The route of the path has no handler for the method of the request
+    MethodNotAllowed,
     UnhandledDbError(Arc<sqlx::Error>),
     Unhandled(Arc<anyhow::Error>),
 }
@@ -15,10 +35,127 @@ pub enum AppError {
 #[derive(Clone, Copy, Debug)]
 pub enum DbError {
     NotFound,
-    #[allow(unused)]
+    /// The resource is not at the version the update was requested for (`If-Match`),
+    /// or was modified concurrently while being updated
     Conflict,
 }
 
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum IdempotencyError {
+    /// The `Idempotency-Key` header is empty or too long
+    InvalidKey,
+    /// The key was used before with a different request body
+    KeyReused,
+}
+
+/// Stable codes of the errors, clients should switch on the code rather than on the status or title
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema, strum::IntoStaticStr)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "kebab-case")]
+pub enum ErrorCode {
+    NotFound,
+    MethodNotAllowed,
+    VersionConflict,
+    ValidationFailed,
+    MalformedRequest,
+    InvalidIdempotencyKey,
+    IdempotencyKeyReused,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "Not found",
+            ErrorCode::MethodNotAllowed => "Method not allowed",
+            ErrorCode::VersionConflict => "Conflict",
+            ErrorCode::ValidationFailed => "Validation failed",
+            ErrorCode::MalformedRequest => "Malformed request",
+            ErrorCode::InvalidIdempotencyKey => "Invalid idempotency key",
+            ErrorCode::IdempotencyKeyReused => "Idempotency key reused",
+            ErrorCode::InternalError => "Internal Server Error",
+        }
+    }
+
+    /// Relative uri of the problem type, eg. `/problems/version-conflict`
+    pub fn problem_type(&self) -> String {
+        let slug: &'static str = self.into();
+        format!("/problems/{slug}")
+    }
+}
+
+/// RFC 7807 problem details, the body of all the error responses
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    #[schema(example = "/problems/version-conflict")]
+    pub r#type: String,
+    #[schema(value_type = String)]
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    pub code: ErrorCode,
+    /// Invalid fields of a [`ErrorCode::ValidationFailed`] problem
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        match serde_json::to_vec(&self) {
+            Ok(body) => (status, [(CONTENT_TYPE, PROBLEM_CONTENT_TYPE)], body).into_response(),
+            Err(e) => {
+                tracing::error!("Unable to serialize problem {:?}: {}", self, e);
+                status.into_response()
+            }
+        }
+    }
+}
+
+/// Renders the errors of the router itself as problems, namely unmatched paths and methods,
+/// with the path of the request as the `instance` of all the problems
+pub fn problem_responses(router: Router) -> Router {
+    router
+        .fallback(no_route)
+        .layer(middleware::from_fn(method_not_allowed))
+        .layer(middleware::from_fn(problem_instance))
+}
+
+/// Middleware that sets the path of the request as the `instance` of its error responses
+async fn problem_instance<B>(req: Request<B>, next: Next<B>) -> Response {
+    let instance = req.uri().path().to_owned();
+    INSTANCE.scope(instance, next.run(req)).await
+}
+
+async fn no_route() -> AppError {
+    AppError::NoRoute
+}
+
+/// Middleware replacing the empty `405` responses of the router by problems
+async fn method_not_allowed<B>(req: Request<B>, next: Next<B>) -> Response {
+    let response = next.run(req).await;
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED
+        || response.headers().contains_key(CONTENT_TYPE)
+    {
+        return response;
+    }
+
+    let mut problem = AppError::MethodNotAllowed.into_response();
+    if let Some(allow) = response.headers().get(ALLOW) {
+        problem.headers_mut().insert(ALLOW, allow.clone());
+    }
+    problem
+}
+
 impl From<sqlx::Error> for AppError {
     fn from(e: sqlx::Error) -> Self {
         AppError::UnhandledDbError(Arc::new(e))
@@ -31,10 +168,54 @@ impl From<anyhow::Error> for AppError {
     }
 }
 
+impl From<JsonRejection> for AppError {
+    fn from(r: JsonRejection) -> Self {
+        AppError::Rejection(r.status(), r.body_text())
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(r: PathRejection) -> Self {
+        AppError::Rejection(r.status(), r.body_text())
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(r: QueryRejection) -> Self {
+        AppError::Rejection(r.status(), r.body_text())
+    }
+}
+
+// A missing extension is a bug of the routing rather than of the request
+impl From<ExtensionRejection> for AppError {
+    fn from(r: ExtensionRejection) -> Self {
+        AppError::Unhandled(Arc::new(anyhow::anyhow!(r.body_text())))
+    }
+}
+
+impl AppError {
+    pub fn problem(&self) -> Problem {
+        let (code, detail, _, status_code) = match_error(self);
+        let fields = match self {
+            AppError::Validation(fields) => fields.clone(),
+            _ => vec![],
+        };
+        Problem {
+            r#type: code.problem_type(),
+            title: code.title(),
+            status: status_code.as_u16(),
+            detail,
+            instance: INSTANCE.try_with(|instance| instance.clone()).ok(),
+            code,
+            fields,
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        // Map the error into error message, log message and status code
-        let (error_message, log_message, status_code) = match_error(&self);
+        // Map the error into error code, log message and status code
+        let (_, _, log_message, status_code) = match_error(&self);
 
         // Log message depending on status code
         if status_code == StatusCode::INTERNAL_SERVER_ERROR {
@@ -44,30 +225,74 @@ impl IntoResponse for AppError {
             tracing::warn!("{:?}", log_message);
         }
 
-        // Build response body with error message
-        let body = Json(json!({ "error": error_message }));
-
-        (status_code, body).into_response()
+        self.problem().into_response()
     }
 }
 
-pub fn match_error(error: &AppError) -> (&str, String, StatusCode) {
+/// Maps the error into its code, the detail returned to clients, the log message and status code
+pub fn match_error(error: &AppError) -> (ErrorCode, String, String, StatusCode) {
     match error {
         AppError::DbError(e) => match e {
-            DbError::Conflict => ("Conflict", "Outdated resource".into(), StatusCode::CONFLICT),
+            DbError::Conflict => (
+                ErrorCode::VersionConflict,
+                "The resource was modified, fetch its current version and retry".into(),
+                "Outdated resource".into(),
+                StatusCode::CONFLICT,
+            ),
             DbError::NotFound => (
-                "Not found",
+                ErrorCode::NotFound,
+                "The resource does not exist".into(),
                 "DB Entry not found".into(),
                 StatusCode::NOT_FOUND,
             ),
         },
+        AppError::Idempotency(e) => match e {
+            IdempotencyError::InvalidKey => (
+                ErrorCode::InvalidIdempotencyKey,
+                "The Idempotency-Key header must have 1 to 255 characters".into(),
+                "Invalid idempotency key".into(),
+                StatusCode::BAD_REQUEST,
+            ),
+            IdempotencyError::KeyReused => (
+                ErrorCode::IdempotencyKeyReused,
+                "The Idempotency-Key was used with a different request".into(),
+                "Idempotency key reused with a different request".into(),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+        },
+        AppError::Validation(fields) => (
+            ErrorCode::ValidationFailed,
+            "The request has invalid fields".into(),
+            format!("{:?}", fields),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ),
+        AppError::Rejection(status_code, message) => (
+            ErrorCode::MalformedRequest,
+            message.clone(),
+            message.clone(),
+            *status_code,
+        ),
+        AppError::NoRoute => (
+            ErrorCode::NotFound,
+            "No endpoint at this path".into(),
+            "No route".into(),
+            StatusCode::NOT_FOUND,
+        ),
+        AppError::MethodNotAllowed => (
+            ErrorCode::MethodNotAllowed,
+            "The method is not allowed at this path, see the Allow header".into(),
+            "Method not allowed".into(),
+            StatusCode::METHOD_NOT_ALLOWED,
+        ),
         AppError::UnhandledDbError(e) => (
-            "Internal Server Error",
+            ErrorCode::InternalError,
+            "Unexpected error".into(),
             format!("{:?}", e),
             StatusCode::INTERNAL_SERVER_ERROR,
         ),
         AppError::Unhandled(e) => (
-            "Internal Server Error",
+            ErrorCode::InternalError,
+            "Unexpected error".into(),
             format!("{:?}", e),
             StatusCode::INTERNAL_SERVER_ERROR,
         ),
@@ -76,13 +301,87 @@ pub fn match_error(error: &AppError) -> (&str, String, StatusCode) {
 
 impl Display for AppError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let (error_message, log_message, status_code) = match_error(self);
+        let (_, detail, log_message, status_code) = match_error(self);
         // This is a workaround to log error details from graphql requests
         if status_code == StatusCode::INTERNAL_SERVER_ERROR {
             tracing::error!("{:?}", log_message);
         }
-        write!(f, "{}", error_message)
+        write!(f, "{}", detail)
     }
 }
 
 impl std::error::Error for AppError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::extract::{Extension, Path};
+    use axum::body::{Body, HttpBody};
+    use axum::routing::get;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn fetch_problem(router: Router, method: &str, uri: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = problem_responses(router).oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers()[CONTENT_TYPE], PROBLEM_CONTENT_TYPE);
+        let status = response.status();
+        let body = response.into_body().data().await.unwrap().unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/claims/:id",
+                get(|Path(id): Path<i32>| async move { id.to_string() }),
+            )
+            .route(
+                "/context",
+                get(|Extension(n): Extension<i32>| async move { n.to_string() }),
+            )
+    }
+
+    #[tokio::test]
+    async fn unmatched_paths_are_problems() {
+        let (status, problem) = fetch_problem(router(), "GET", "/unknown").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(problem["type"], "/problems/not-found");
+        assert_eq!(problem["code"], "NOT_FOUND");
+        assert_eq!(problem["instance"], "/unknown");
+    }
+
+    #[tokio::test]
+    async fn unmatched_methods_are_problems() {
+        let (status, problem) = fetch_problem(router(), "DELETE", "/claims/1").await;
+
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(problem["type"], "/problems/method-not-allowed");
+        assert_eq!(problem["code"], "METHOD_NOT_ALLOWED");
+    }
+
+    #[tokio::test]
+    async fn path_rejections_are_problems() {
+        let (status, problem) = fetch_problem(router(), "GET", "/claims/abc").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(problem["type"], "/problems/malformed-request");
+        assert_eq!(problem["code"], "MALFORMED_REQUEST");
+        assert_eq!(problem["instance"], "/claims/abc");
+    }
+
+    #[tokio::test]
+    async fn extension_rejections_are_problems() {
+        let (status, problem) = fetch_problem(router(), "GET", "/context").await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(problem["type"], "/problems/internal-error");
+        assert_eq!(problem["code"], "INTERNAL_ERROR");
+    }
+}
@@ -0,0 +1,220 @@
+use crate::api::rest::extract::Extension;
+use crate::common::api::ApiContext;
+use crate::config::Health;
+use crate::db;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::migrate::Migrate;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::time::Duration;
+
+/// Maximum time a single readiness check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Status {
+    Up,
+    /// Working but not fully, eg. an optional dependency is missing, does not fail readiness
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentHealth {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl ComponentHealth {
+    fn up(details: Value) -> Self {
+        Self {
+            status: Status::Up,
+            details: Some(details),
+        }
+    }
+
+    fn degraded(details: Value) -> Self {
+        Self {
+            status: Status::Degraded,
+            details: Some(details),
+        }
+    }
+
+    fn down(details: Value) -> Self {
+        Self {
+            status: Status::Down,
+            details: Some(details),
+        }
+    }
+}
+
+/// Liveness, the process is up and serving requests
+pub async fn liveness() -> impl IntoResponse {
+    Json(json!({ "status" : Status::Up }))
+}
+
+/// Readiness, all dependencies needed to serve requests are available
+pub async fn readiness(Extension(context): Extension<ApiContext>) -> impl IntoResponse {
+    let (db, schema_registry, migrations, outbox) = tokio::join!(
+        check(check_db(&context.db)),
+        check(check_schema_registry(&context.config.schema_registry.url)),
+        check(check_migrations(&context.db)),
+        check(check_outbox(&context.db, &context.config.health)),
+    );
+
+    let components = BTreeMap::from([
+        ("db", db),
+        ("schemaRegistry", schema_registry),
+        ("migrations", migrations),
+        ("outbox", outbox),
+    ]);
+
+    let (status, status_code) = aggregate(&components);
+    (
+        status_code,
+        Json(json!({ "status": status, "components": components })),
+    )
+}
+
+/// Overall readiness, down if any component is down
+fn aggregate(components: &BTreeMap<&str, ComponentHealth>) -> (Status, StatusCode) {
+    let statuses: Vec<Status> = components.values().map(|c| c.status).collect();
+    if statuses.contains(&Status::Down) {
+        (Status::Down, StatusCode::SERVICE_UNAVAILABLE)
+    } else if statuses.contains(&Status::Degraded) {
+        (Status::Degraded, StatusCode::OK)
+    } else {
+        (Status::Up, StatusCode::OK)
+    }
+}
+
+/// Runs a single check, any error or timeout marks the component as down
+async fn check<F>(check: F) -> ComponentHealth
+where
+    F: Future<Output = anyhow::Result<ComponentHealth>>,
+{
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(health)) => health,
+        Ok(Err(e)) => ComponentHealth::down(json!({ "error": e.to_string() })),
+        Err(_) => ComponentHealth::down(json!({ "error": "Check timed out" })),
+    }
+}
+
+async fn check_db(db: &PgPool) -> anyhow::Result<ComponentHealth> {
+    sqlx::query("SELECT 1").execute(db).await?;
+    Ok(ComponentHealth::up(json!({
+        "connections": db.size(),
+        "idleConnections": db.num_idle(),
+    })))
+}
+
+async fn check_schema_registry(url: &str) -> anyhow::Result<ComponentHealth> {
+    let url = format!("{}/subjects", url.trim_end_matches('/'));
+    let response = reqwest::get(url).await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Ok(ComponentHealth::down(
+            json!({ "error": format!("Unexpected status {status}") }),
+        ));
+    }
+
+    let subjects: Vec<String> = response.json().await?;
+    Ok(ComponentHealth::up(json!({ "subjects": subjects.len() })))
+}
+
+async fn check_migrations(db: &PgPool) -> anyhow::Result<ComponentHealth> {
+    let mut conn = db.acquire().await?;
+    let applied: HashSet<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect();
+
+    let pending: Vec<i64> = db::MIGRATOR
+        .iter()
+        .map(|m| m.version)
+        .filter(|version| !applied.contains(version))
+        .collect();
+
+    if pending.is_empty() {
+        Ok(ComponentHealth::up(json!({ "applied": applied.len() })))
+    } else {
+        Ok(ComponentHealth::down(json!({ "pending": pending })))
+    }
+}
+
+/// The outbox table is emptied on write, so the backlog is the WAL debezium has not yet
+/// confirmed through its replication slot.
+///
+/// The slot is created by the debezium connector once it starts, which may well be after the
+/// service is ready (eg. in a fresh environment), so a missing slot only degrades the outbox.
+async fn check_outbox(db: &PgPool, config: &Health) -> anyhow::Result<ComponentHealth> {
+    let row: Option<(Option<i64>,)> = sqlx::query_as(
+        r#"SELECT pg_wal_lsn_diff(pg_current_wal_lsn(), confirmed_flush_lsn)::BIGINT
+            FROM pg_replication_slots
+            WHERE slot_name = $1"#,
+    )
+    .bind(&config.outbox_slot)
+    .fetch_optional(db)
+    .await?;
+
+    let Some((lag,)) = row else {
+        return Ok(ComponentHealth::degraded(json!({
+            "slot": config.outbox_slot,
+            "warning": "Replication slot not found, the outbox connector has not started yet"
+        })));
+    };
+
+    let lag = lag.unwrap_or_default();
+    let details = json!({ "slot": config.outbox_slot, "lagBytes": lag });
+    if lag > config.outbox_max_lag_bytes {
+        Ok(ComponentHealth::down(details))
+    } else {
+        Ok(ComponentHealth::up(details))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components(statuses: &[Status]) -> BTreeMap<&'static str, ComponentHealth> {
+        let names = ["db", "schemaRegistry", "migrations", "outbox"];
+        names
+            .into_iter()
+            .zip(statuses)
+            .map(|(name, status)| {
+                let health = ComponentHealth {
+                    status: *status,
+                    details: None,
+                };
+                (name, health)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ready_unless_a_component_is_down() {
+        assert_eq!(
+            aggregate(&components(&[Status::Up, Status::Up])),
+            (Status::Up, StatusCode::OK)
+        );
+        assert_eq!(
+            aggregate(&components(&[Status::Up, Status::Degraded])),
+            (Status::Degraded, StatusCode::OK)
+        );
+        assert_eq!(
+            aggregate(&components(&[Status::Degraded, Status::Down])),
+            (Status::Down, StatusCode::SERVICE_UNAVAILABLE)
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use crate::api::rest::extract::Extension;
+use crate::common::api::ApiContext;
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+use std::time::Instant;
+
+pub const HTTP_REQUESTS: &str = "http_requests_total";
+pub const HTTP_REQUESTS_DURATION: &str = "http_requests_duration_seconds";
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+pub const OUTBOX_EVENTS: &str = "outbox_events_total";
+
+/// Registers the descriptions of the service metrics with the installed recorder
+pub fn describe() {
+    describe_counter!(HTTP_REQUESTS, "Http requests by method, route and status");
+    describe_histogram!(
+        HTTP_REQUESTS_DURATION,
+        Unit::Seconds,
+        "Http requests latency by method, route and status"
+    );
+    describe_gauge!(DB_POOL_CONNECTIONS, "Open connections of the database pool");
+    describe_gauge!(
+        DB_POOL_IDLE_CONNECTIONS,
+        "Idle connections of the database pool"
+    );
+    describe_counter!(
+        OUTBOX_EVENTS,
+        "Events written to the outbox table by aggregate type and event type"
+    );
+}
+
+/// Middleware that records request count and latency per matched route
+/// see: https://github.com/tokio-rs/axum/blob/v0.6.x/examples/prometheus-metrics/src/main.rs
+pub async fn track_metrics<B>(req: Request<B>, next: Next<B>) -> impl IntoResponse {
+    let start = Instant::now();
+    let path = if let Some(matched_path) = req.extensions().get::<MatchedPath>() {
+        matched_path.as_str().to_owned()
+    } else {
+        req.uri().path().to_owned()
+    };
+    let method = req.method().to_string();
+
+    let response = next.run(req).await;
+
+    let latency = start.elapsed();
+    let status = response.status().as_u16().to_string();
+    let labels = [("method", method), ("path", path), ("status", status)];
+
+    metrics::increment_counter!(HTTP_REQUESTS, &labels);
+    metrics::histogram!(HTTP_REQUESTS_DURATION, latency, &labels);
+
+    response
+}
+
+/// Renders all recorded metrics in the prometheus exposition format
+pub async fn render_metrics(Extension(context): Extension<ApiContext>) -> impl IntoResponse {
+    // Pool statistics are sampled on every scrape
+    metrics::gauge!(DB_POOL_CONNECTIONS, context.db.size() as f64);
+    metrics::gauge!(DB_POOL_IDLE_CONNECTIONS, context.db.num_idle() as f64);
+
+    context.metrics.render()
+}
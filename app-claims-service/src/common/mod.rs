@@ -1,3 +1,8 @@
 pub mod api;
+pub mod correlation;
 pub mod error;
+pub mod health;
+pub mod metrics;
 pub mod misc;
+pub mod trace;
+pub mod validation;
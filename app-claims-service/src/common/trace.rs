@@ -0,0 +1,47 @@
+use axum::extract::MatchedPath;
+use axum::http::{HeaderMap, Request};
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use claims_core::correlation::CorrelationId;
+use claims_core::otel;
+use opentelemetry::propagation::Extractor;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Middleware that handles every request in its own span, continuing the trace of the caller
+/// given a W3C `traceparent` header
+pub async fn trace_request<B>(req: Request<B>, next: Next<B>) -> impl IntoResponse {
+    let path = if let Some(matched_path) = req.extensions().get::<MatchedPath>() {
+        matched_path.as_str().to_owned()
+    } else {
+        req.uri().path().to_owned()
+    };
+
+    let span = tracing::info_span!(
+        "http_request",
+        method = %req.method(),
+        path = %path,
+        correlation_id = tracing::field::Empty,
+        status = tracing::field::Empty
+    );
+    if let Some(correlation_id) = req.extensions().get::<CorrelationId>() {
+        span.record("correlation_id", correlation_id.as_str());
+    }
+    span.set_parent(otel::extract(&HeaderExtractor(req.headers())));
+
+    let response = next.run(req).instrument(span.clone()).await;
+    span.record("status", response.status().as_u16());
+    response
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
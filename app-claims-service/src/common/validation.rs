@@ -0,0 +1,239 @@
+use crate::common::error::{AppError, FieldError};
+use claims_model::model::{PartyData, PartySubtype, Person, Vehicle};
+
+const MAX_NAME_LEN: usize = 255;
+
+/// Validation of a request resource
+pub trait Validate {
+    /// Adds to the errors the invalid fields of the value found under `field`
+    fn validate(&self, field: &str, errors: &mut ValidationErrors);
+}
+
+/// Invalid fields of a request, collected so that all of them are reported at once
+#[derive(Debug, Default)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn error<M: Into<String>>(&mut self, field: String, message: M) {
+        self.0.push(FieldError {
+            field,
+            message: message.into(),
+        });
+    }
+
+    /// Reports the field with the message unless valid
+    pub fn check<M: Into<String>>(&mut self, valid: bool, field: String, message: M) {
+        if !valid {
+            self.error(field, message);
+        }
+    }
+
+    /// Fails with [`AppError::Validation`] if any field is invalid
+    pub fn into_result(self) -> Result<(), AppError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.0))
+        }
+    }
+}
+
+/// Validates a whole request resource
+pub fn validate<T: Validate>(value: &T) -> Result<(), AppError> {
+    let mut errors = ValidationErrors::default();
+    value.validate("", &mut errors);
+    errors.into_result()
+}
+
+/// Path of the `field` of the value under `parent`
+pub fn join_field(parent: &str, field: &str) -> String {
+    if parent.is_empty() {
+        field.into()
+    } else {
+        format!("{parent}.{field}")
+    }
+}
+
+impl Validate for PartyData {
+    fn validate(&self, field: &str, errors: &mut ValidationErrors) {
+        match self {
+            PartyData::Person(person) => person.validate(field, errors),
+            PartyData::Vehicle(vehicle) => vehicle.validate(field, errors),
+        }
+    }
+}
+
+impl Validate for Person {
+    fn validate(&self, field: &str, errors: &mut ValidationErrors) {
+        errors.check(
+            matches!(
+                self.subtype,
+                PartySubtype::Owner
+                    | PartySubtype::Beneficiary
+                    | PartySubtype::Driver
+                    | PartySubtype::Passenger
+                    | PartySubtype::Other
+            ),
+            join_field(field, "subtype"),
+            "must be one of OWNER, BENEFICIARY, DRIVER, PASSENGER, OTHER for a person",
+        );
+        validate_name(&self.name, join_field(field, "name"), errors);
+    }
+}
+
+impl Validate for Vehicle {
+    fn validate(&self, field: &str, errors: &mut ValidationErrors) {
+        errors.check(
+            matches!(
+                self.subtype,
+                PartySubtype::Car | PartySubtype::Motorbike | PartySubtype::Other
+            ),
+            join_field(field, "subtype"),
+            "must be one of CAR, MOTORBIKE, OTHER for a vehicle",
+        );
+        errors.check(
+            is_reg_no(&self.reg_no),
+            join_field(field, "regNo"),
+            "must be 2 to 12 letters or digits, optionally separated by single dashes or spaces",
+        );
+        if let Some(make) = &self.make {
+            validate_name(make, join_field(field, "make"), errors);
+        }
+        if let Some(model) = &self.model {
+            validate_name(model, join_field(field, "model"), errors);
+        }
+    }
+}
+
+fn validate_name(name: &str, field: String, errors: &mut ValidationErrors) {
+    if name.trim().is_empty() {
+        errors.error(field, "must not be empty");
+    } else if name.chars().count() > MAX_NAME_LEN {
+        errors.error(field, format!("must not exceed {MAX_NAME_LEN} characters"));
+    }
+}
+
+/// Registration numbers such as `ABC-1234`, `AB 12 CD` or `XYZ123`
+fn is_reg_no(reg_no: &str) -> bool {
+    let alphanumerics = reg_no.chars().filter(char::is_ascii_alphanumeric).count();
+    (2..=12).contains(&alphanumerics)
+        && reg_no
+            .split(['-', ' '])
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields<T: Validate>(value: &T) -> Vec<String> {
+        let mut errors = ValidationErrors::default();
+        value.validate("data", &mut errors);
+        errors.0.into_iter().map(|e| e.field).collect()
+    }
+
+    fn person(subtype: PartySubtype, name: &str) -> Person {
+        Person {
+            subtype,
+            name: name.into(),
+        }
+    }
+
+    fn vehicle(subtype: PartySubtype, reg_no: &str) -> Vehicle {
+        Vehicle {
+            subtype,
+            reg_no: reg_no.into(),
+            make: None,
+            model: None,
+        }
+    }
+
+    #[test]
+    fn names_are_required() {
+        assert!(fields(&person(PartySubtype::Driver, "Jane Doe")).is_empty());
+        assert_eq!(fields(&person(PartySubtype::Driver, "")), ["data.name"]);
+        assert_eq!(fields(&person(PartySubtype::Driver, "  ")), ["data.name"]);
+    }
+
+    #[test]
+    fn names_are_limited_in_characters_rather_than_bytes() {
+        let max = "é".repeat(MAX_NAME_LEN);
+        assert!(fields(&person(PartySubtype::Driver, &max)).is_empty());
+
+        let above = "e".repeat(MAX_NAME_LEN + 1);
+        assert_eq!(fields(&person(PartySubtype::Driver, &above)), ["data.name"]);
+    }
+
+    #[test]
+    fn person_subtypes_are_of_persons() {
+        for subtype in [
+            PartySubtype::Owner,
+            PartySubtype::Beneficiary,
+            PartySubtype::Driver,
+            PartySubtype::Passenger,
+            PartySubtype::Other,
+        ] {
+            assert!(fields(&person(subtype, "Jane Doe")).is_empty());
+        }
+        for subtype in [PartySubtype::Car, PartySubtype::Motorbike] {
+            assert_eq!(fields(&person(subtype, "Jane Doe")), ["data.subtype"]);
+        }
+    }
+
+    #[test]
+    fn vehicle_subtypes_are_of_vehicles() {
+        for subtype in [
+            PartySubtype::Car,
+            PartySubtype::Motorbike,
+            PartySubtype::Other,
+        ] {
+            assert!(fields(&vehicle(subtype, "ABC-1234")).is_empty());
+        }
+        for subtype in [PartySubtype::Owner, PartySubtype::Driver] {
+            assert_eq!(fields(&vehicle(subtype, "ABC-1234")), ["data.subtype"]);
+        }
+    }
+
+    #[test]
+    fn validates_registration_numbers() {
+        for reg_no in ["ABC-1234", "AB 12 CD", "XYZ123", "A1"] {
+            assert!(is_reg_no(reg_no), "{reg_no}");
+        }
+        for reg_no in [
+            "",
+            "A",
+            "ABCDEFGHIJ123",
+            "AB--12",
+            "-AB12",
+            "AB12 ",
+            "AB_12",
+            "ΑΒ12",
+        ] {
+            assert!(!is_reg_no(reg_no), "{reg_no}");
+        }
+        assert_eq!(fields(&vehicle(PartySubtype::Car, "A")), ["data.regNo"]);
+    }
+
+    #[test]
+    fn vehicle_make_and_model_are_optional_names() {
+        let mut car = vehicle(PartySubtype::Car, "ABC-1234");
+        car.make = Some("Volvo".into());
+        car.model = Some("V60".into());
+        assert!(fields(&car).is_empty());
+
+        car.make = Some("".into());
+        car.model = Some("m".repeat(MAX_NAME_LEN + 1));
+        assert_eq!(fields(&car), ["data.make", "data.model"]);
+    }
+
+    #[test]
+    fn reports_the_invalid_fields_of_the_party_data() {
+        let data = PartyData::Vehicle(vehicle(PartySubtype::Driver, ""));
+
+        assert_eq!(fields(&data), ["data.subtype", "data.regNo"]);
+        match validate(&data) {
+            Err(AppError::Validation(errors)) => assert_eq!(errors.len(), 2),
+            _ => panic!("Expected validation errors"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use claims_core::config::{Database, SchemaRegistry, Server};
+use claims_core::config::validate::join_key;
+use claims_core::config::{Admin, Database, SchemaRegistry, Server, Validate, ValidationReport};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -7,4 +8,104 @@ pub struct AppConfig {
     pub db: Database,
     pub schema_registry: SchemaRegistry,
     pub server: Server,
+    /// Grpc server of the claims api, not started when missing
+    #[serde(default)]
+    pub grpc: Option<Server>,
+    /// Listener of the admin endpoints, not started when missing
+    #[serde(default)]
+    pub admin: Option<Admin>,
+    #[serde(default)]
+    pub health: Health,
+    #[serde(default)]
+    pub idempotency: Idempotency,
+    /// Seconds the components are given to stop on shutdown
+    #[serde(
+        rename = "graceful-shutdown",
+        default = "claims_core::config::default_graceful_shutdown"
+    )]
+    pub graceful_shutdown: u64,
+}
+
+impl Validate for AppConfig {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        self.log.validate(&join_key(key, "log"), report);
+        self.db.validate(&join_key(key, "db"), report);
+        self.schema_registry
+            .validate(&join_key(key, "schema_registry"), report);
+        self.server.validate(&join_key(key, "server"), report);
+        if let Some(grpc) = &self.grpc {
+            grpc.validate(&join_key(key, "grpc"), report);
+        }
+        if let Some(admin) = &self.admin {
+            admin.validate(&join_key(key, "admin"), report);
+        }
+        self.health.validate(&join_key(key, "health"), report);
+        self.idempotency
+            .validate(&join_key(key, "idempotency"), report);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Health {
+    /// Replication slot of the debezium outbox connector
+    pub outbox_slot: String,
+    /// WAL not yet confirmed by the outbox connector above which the service is not ready
+    pub outbox_max_lag_bytes: i64,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            outbox_slot: "debezium".into(),
+            outbox_max_lag_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl Validate for Health {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            !self.outbox_slot.is_empty(),
+            join_key(key, "outbox_slot"),
+            "must not be empty",
+        );
+        report.check(
+            self.outbox_max_lag_bytes > 0,
+            join_key(key, "outbox_max_lag_bytes"),
+            "must be greater than 0",
+        );
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Idempotency {
+    /// Seconds an idempotency key is kept, a retry after that is handled as a new request
+    pub ttl_secs: u64,
+    /// Seconds between the purges of the expired keys
+    pub purge_interval_secs: u64,
+}
+
+impl Default for Idempotency {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 24 * 60 * 60,
+            purge_interval_secs: 60 * 60,
+        }
+    }
+}
+
+impl Validate for Idempotency {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            self.ttl_secs > 0,
+            join_key(key, "ttl_secs"),
+            "must be greater than 0",
+        );
+        report.check(
+            self.purge_interval_secs > 0,
+            join_key(key, "purge_interval_secs"),
+            "must be greater than 0",
+        );
+    }
 }
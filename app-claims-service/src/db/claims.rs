@@ -1,32 +1,130 @@
 use crate::db::entities::ClaimDb;
 use crate::db::PostgresTx;
-use sqlx::{Executor, Postgres};
+use claims_model::model::{ClaimStatus, IncidentType};
+use sqlx::{Executor, Postgres, QueryBuilder};
 
 // For implementation details of these functions
 // see examples:
 //  - https://github.com/govinda-attal/app-a/blob/main/src/db/functions.rs
 //  - https://github.com/launchbadge/sqlx/blob/main/examples/postgres/transaction/src/main.rs#L3-
 
-pub async fn fetch_all(
+/// Filters of the claims listing, all of them optional
+#[derive(Default)]
+pub struct ClaimFilter {
+    pub status: Option<ClaimStatus>,
+    pub incident_type: Option<IncidentType>,
+    /// Matches the claims whose number starts with the given prefix
+    pub claim_no: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClaimSort {
+    #[default]
+    Id,
+    ClaimNo,
+    Status,
+    IncidentType,
+}
+
+impl ClaimSort {
+    fn column(self) -> &'static str {
+        match self {
+            ClaimSort::Id => "id",
+            ClaimSort::ClaimNo => "claim_no",
+            ClaimSort::Status => "status",
+            ClaimSort::IncidentType => "incident_type",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn keyword(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// A page of the claims matching the filter, ordered by `sort` (and id to keep pages stable)
+pub async fn fetch_page(
     con: impl Executor<'_, Database = Postgres>,
+    filter: &ClaimFilter,
+    sort: ClaimSort,
+    order: SortOrder,
+    offset: i64,
+    limit: i64,
 ) -> anyhow::Result<Vec<ClaimDb>> {
-    let rows: Vec<ClaimDb> =
-        sqlx::query_as(r#"SELECT id, claim_no, incident_type, status FROM claim"#)
-            .fetch_all(con)
-            .await?;
+    let mut query =
+        QueryBuilder::new("SELECT id, claim_no, incident_type, status, version FROM claim");
+    push_filter(&mut query, filter);
+    query.push(format_args!(
+        " ORDER BY {} {}",
+        sort.column(),
+        order.keyword()
+    ));
+    if !matches!(sort, ClaimSort::Id) {
+        query.push(format_args!(", id {}", order.keyword()));
+    }
+    query.push(" LIMIT ").push_bind(limit);
+    query.push(" OFFSET ").push_bind(offset);
 
+    let rows: Vec<ClaimDb> = query.build_query_as().fetch_all(con).await?;
     Ok(rows)
 }
 
+/// Number of claims matching the filter
+pub async fn count(
+    con: impl Executor<'_, Database = Postgres>,
+    filter: &ClaimFilter,
+) -> anyhow::Result<i64> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM claim");
+    push_filter(&mut query, filter);
+
+    let (count,): (i64,) = query.build_query_as().fetch_one(con).await?;
+    Ok(count)
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &ClaimFilter) {
+    query.push(" WHERE TRUE");
+    if let Some(status) = filter.status {
+        query.push(" AND status = ").push_bind(status);
+    }
+    if let Some(incident_type) = filter.incident_type {
+        query.push(" AND incident_type = ").push_bind(incident_type);
+    }
+    if let Some(claim_no) = &filter.claim_no {
+        query
+            .push(" AND claim_no LIKE ")
+            .push_bind(format!("{}%", escape_like(claim_no)));
+    }
+}
+
+/// Escapes the LIKE wildcards so that the value is matched literally
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 pub async fn fetch_one(
     con: impl Executor<'_, Database = Postgres>,
     id: i32,
 ) -> anyhow::Result<Option<ClaimDb>> {
-    let row: Option<ClaimDb> =
-        sqlx::query_as(r#"SELECT id, claim_no, incident_type, status FROM claim WHERE id = $1"#)
-            .bind(id)
-            .fetch_optional(con)
-            .await?;
+    let row: Option<ClaimDb> = sqlx::query_as(
+        r#"SELECT id, claim_no, incident_type, status, version FROM claim WHERE id = $1"#,
+    )
+    .bind(id)
+    .fetch_optional(con)
+    .await?;
 
     Ok(row)
 }
@@ -45,14 +143,34 @@ pub async fn create(tx: &mut PostgresTx<'_>, c: ClaimDb) -> anyhow::Result<Claim
     Ok(row)
 }
 
-pub async fn update(tx: &mut PostgresTx<'_>, c: ClaimDb) -> anyhow::Result<ClaimDb> {
-    let row: ClaimDb = sqlx::query_as(
-        r#"UPDATE claim SET incident_type = $1, status = $2 WHERE id = $3 RETURNING *"#,
+/// Updates the claim unless it has changed since read, returns `None` if its version is outdated
+pub async fn update(tx: &mut PostgresTx<'_>, c: ClaimDb) -> anyhow::Result<Option<ClaimDb>> {
+    let row: Option<ClaimDb> = sqlx::query_as(
+        r#"UPDATE claim
+            SET incident_type = $1, status = $2, version = version + 1
+            WHERE id = $3 AND version = $4
+            RETURNING *"#,
     )
     .bind(c.incident_type)
     .bind(c.status)
     .bind(c.id)
-    .fetch_one(&mut **tx)
+    .bind(c.version)
+    .fetch_optional(&mut **tx)
     .await?;
     Ok(row)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::escape_like;
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like("AB-12"), "AB-12");
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("AB_12"), "AB\\_12");
+        assert_eq!(escape_like("AB\\12"), "AB\\\\12");
+        // The escape character is escaped first, so the added ones are not doubled
+        assert_eq!(escape_like("\\%_"), "\\\\\\%\\_");
+    }
+}
@@ -10,6 +10,7 @@ pub struct ClaimDb {
     pub status: ClaimStatus,
     pub claim_no: String,
     pub incident_type: IncidentType,
+    pub version: i32,
 }
 
 impl ClaimDb {
@@ -29,12 +30,14 @@ impl From<ClaimDb> for Claim {
             claim_no,
             incident_type,
             status,
+            version,
         } = v;
         Self {
             id,
             claim_no,
             incident_type,
             status,
+            version,
         }
     }
 }
@@ -46,6 +49,7 @@ pub struct PartyDb {
     pub r#type: PartyType,
     pub subtype: PartySubtype,
     pub data: Json<PartyData>,
+    pub version: i32,
 }
 
 impl PartyDb {
@@ -56,6 +60,7 @@ impl PartyDb {
             r#type: data.r#type(),
             subtype: data.subtype(),
             data: Json(data),
+            version: 0,
         }
     }
 }
@@ -68,6 +73,7 @@ impl From<PartyDb> for Party {
             r#type,
             subtype,
             data,
+            version,
         } = v;
 
         Self {
@@ -76,6 +82,7 @@ impl From<PartyDb> for Party {
             r#type,
             subtype,
             data: data.0,
+            version,
         }
     }
 }
@@ -87,4 +94,14 @@ pub struct ClaimOutboxEventDb {
     pub aggregateid: String,
     pub r#type: String,
     pub payload: Vec<u8>,
+    pub traceparent: Option<String>,
+    pub tracestate: Option<String>,
+    pub correlation_id: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct IdempotencyKeyDb {
+    pub request: Json<serde_json::Value>,
+    /// Missing until the request that reserved the key completes, in the same transaction
+    pub response: Option<Json<serde_json::Value>>,
 }
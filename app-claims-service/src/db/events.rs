@@ -1,3 +1,4 @@
+use crate::common::metrics::OUTBOX_EVENTS;
 use crate::db::entities::ClaimOutboxEventDb;
 use crate::db::PostgresTx;
 
@@ -6,6 +7,11 @@ pub async fn send_event<E: Into<ClaimOutboxEventDb>>(
     e: E,
 ) -> anyhow::Result<ClaimOutboxEventDb> {
     let inserted = create_event(tx, e.into()).await?;
+    metrics::increment_counter!(
+        OUTBOX_EVENTS,
+        "aggregate_type" => inserted.aggregatetype.clone(),
+        "type" => inserted.r#type.clone()
+    );
     delete_event(tx, inserted).await
 }
 
@@ -14,12 +20,15 @@ pub async fn create_event(
     e: ClaimOutboxEventDb,
 ) -> anyhow::Result<ClaimOutboxEventDb> {
     let row: ClaimOutboxEventDb = sqlx::query_as(
-        r#"INSERT INTO claim_outbox_event (aggregatetype, aggregateid, "type", payload) VALUES ($1, $2, $3, $4) RETURNING *"#,
+        r#"INSERT INTO claim_outbox_event (aggregatetype, aggregateid, "type", payload, traceparent, tracestate, correlation_id) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *"#,
     )
     .bind(e.aggregatetype)
     .bind(e.aggregateid)
     .bind(e.r#type)
     .bind(e.payload)
+    .bind(e.traceparent)
+    .bind(e.tracestate)
+    .bind(e.correlation_id)
 
     // In 0.7, `Transaction` can no longer implement `Executor` directly,
     // so it must be de referenced to the internal connection type.
@@ -0,0 +1,77 @@
+use crate::db::entities::IdempotencyKeyDb;
+use crate::db::PostgresTx;
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{Executor, Postgres};
+use std::time::Duration;
+
+/// Reserves the key for the current transaction, returns `false` if already used within `ttl`.
+///
+/// A key older than `ttl` is reserved again as if it was never used. A concurrent request with
+/// the same key waits for the transaction that reserved it to complete.
+pub async fn reserve(
+    tx: &mut PostgresTx<'_>,
+    key: &str,
+    scope: &str,
+    request: Value,
+    ttl: Duration,
+) -> anyhow::Result<bool> {
+    let result = sqlx::query(
+        r#"INSERT INTO idempotency_key ("key", scope, request) VALUES ($1, $2, $3)
+            ON CONFLICT ("key", scope) DO UPDATE
+            SET request = EXCLUDED.request, response = NULL, created_at = now()
+            WHERE idempotency_key.created_at < now() - make_interval(secs => $4)"#,
+    )
+    .bind(key)
+    .bind(scope)
+    .bind(Json(request))
+    .bind(ttl.as_secs_f64())
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn fetch_one(
+    tx: &mut PostgresTx<'_>,
+    key: &str,
+    scope: &str,
+) -> anyhow::Result<Option<IdempotencyKeyDb>> {
+    let row: Option<IdempotencyKeyDb> = sqlx::query_as(
+        r#"SELECT request, response FROM idempotency_key WHERE "key" = $1 AND scope = $2"#,
+    )
+    .bind(key)
+    .bind(scope)
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row)
+}
+
+/// Stores the response of the request that reserved the key
+pub async fn complete(
+    tx: &mut PostgresTx<'_>,
+    key: &str,
+    scope: &str,
+    response: Value,
+) -> anyhow::Result<()> {
+    sqlx::query(r#"UPDATE idempotency_key SET response = $1 WHERE "key" = $2 AND scope = $3"#)
+        .bind(Json(response))
+        .bind(key)
+        .bind(scope)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Deletes the keys older than `ttl`, returns the number of deleted keys
+pub async fn purge(
+    con: impl Executor<'_, Database = Postgres>,
+    ttl: Duration,
+) -> anyhow::Result<u64> {
+    let result = sqlx::query(
+        r#"DELETE FROM idempotency_key WHERE created_at < now() - make_interval(secs => $1)"#,
+    )
+    .bind(ttl.as_secs_f64())
+    .execute(con)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -1,8 +1,13 @@
+use sqlx::migrate::Migrator;
 use sqlx::{Postgres, Transaction};
 
 pub mod claims;
 pub mod entities;
 pub mod events;
+pub mod idempotency;
 pub mod parties;
 
 pub type PostgresTx<'t> = Transaction<'t, Postgres>;
+
+/// Embedded migrations of the claims schema
+pub static MIGRATOR: Migrator = sqlx::migrate!();
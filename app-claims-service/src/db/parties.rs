@@ -2,15 +2,45 @@ use crate::db::entities::PartyDb;
 use crate::db::PostgresTx;
 use sqlx::{Executor, Postgres};
 
-pub async fn fetch_one(
+pub async fn fetch_by_claim(
     con: impl Executor<'_, Database = Postgres>,
+    claim_id: i32,
+) -> anyhow::Result<Vec<PartyDb>> {
+    let rows: Vec<PartyDb> = sqlx::query_as(
+        r#"SELECT id, claim_id, "type", subtype, data, version FROM party WHERE claim_id = $1 ORDER BY id"#,
+    )
+    .bind(claim_id)
+    .fetch_all(con)
+    .await?;
+    Ok(rows)
+}
+
+/// The parties of all the given claims, in a single query
+pub async fn fetch_by_claims(
+    con: impl Executor<'_, Database = Postgres>,
+    claim_ids: &[i32],
+) -> anyhow::Result<Vec<PartyDb>> {
+    let rows: Vec<PartyDb> = sqlx::query_as(
+        r#"SELECT id, claim_id, "type", subtype, data, version FROM party WHERE claim_id = ANY($1) ORDER BY claim_id, id"#,
+    )
+    .bind(claim_ids)
+    .fetch_all(con)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn fetch_one_by_claim(
+    con: impl Executor<'_, Database = Postgres>,
+    claim_id: i32,
     id: i32,
 ) -> anyhow::Result<Option<PartyDb>> {
-    let row: Option<PartyDb> =
-        sqlx::query_as(r#"SELECT id, claim_id, "type", subtype, data FROM party WHERE id = $1"#)
-            .bind(id)
-            .fetch_optional(con)
-            .await?;
+    let row: Option<PartyDb> = sqlx::query_as(
+        r#"SELECT id, claim_id, "type", subtype, data, version FROM party WHERE id = $1 AND claim_id = $2"#,
+    )
+    .bind(id)
+    .bind(claim_id)
+    .fetch_optional(con)
+    .await?;
     Ok(row)
 }
 
@@ -42,11 +72,12 @@ pub async fn create(tx: &mut PostgresTx<'_>, p: PartyDb) -> anyhow::Result<Party
     Ok(row)
 }
 
-pub async fn update(tx: &mut PostgresTx<'_>, p: PartyDb) -> anyhow::Result<PartyDb> {
-    let row: PartyDb = sqlx::query_as(
+/// Updates the party unless it has changed since read, returns `None` if its version is outdated
+pub async fn update(tx: &mut PostgresTx<'_>, p: PartyDb) -> anyhow::Result<Option<PartyDb>> {
+    let row: Option<PartyDb> = sqlx::query_as(
         r#"UPDATE party
-            SET "type" = $1, subtype = $2, data = $3
-            WHERE id = $4 AND claim_id = $5
+            SET "type" = $1, subtype = $2, data = $3, version = version + 1
+            WHERE id = $4 AND claim_id = $5 AND version = $6
             RETURNING *"#,
     )
     .bind(p.r#type)
@@ -54,7 +85,8 @@ pub async fn update(tx: &mut PostgresTx<'_>, p: PartyDb) -> anyhow::Result<Party
     .bind(p.data)
     .bind(p.id)
     .bind(p.claim_id)
-    .fetch_one(&mut **tx)
+    .bind(p.version)
+    .fetch_optional(&mut **tx)
     .await?;
     Ok(row)
 }
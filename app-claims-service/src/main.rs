@@ -2,14 +2,19 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::common::api::{health, ApiContext};
+use crate::common::api::ApiContext;
+use crate::common::correlation::correlation_id;
+use crate::common::error::problem_responses;
+use crate::common::health::{liveness, readiness};
+use crate::common::metrics::{render_metrics, track_metrics};
+use crate::common::trace::trace_request;
 use crate::config::AppConfig;
+use crate::service::claim_service::ClaimService;
 use crate::service::event_service::EventService;
 use anyhow::Context;
 use axum::routing::get;
-use axum::{Extension, Router};
-use sqlx::postgres::PgPoolOptions;
-use sqlx::{Executor, PgPool};
+use axum::{middleware, Extension, Router};
+use claims_core::shutdown::{ShutdownCoordinator, ShutdownToken};
 
 mod api;
 mod common;
@@ -19,59 +24,93 @@ mod service;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config: AppConfig =
-        claims_core::config::load("./config/application.yml").context("Unable to load config")?;
+    let args = claims_core::config::Args::parse()?;
+    let layers = claims_core::config::load_layers(&args)?;
+    if args.print_config {
+        println!("{}", layers.redacted()?);
+        return Ok(());
+    }
+    let config: AppConfig = layers.try_deserialize().context("Unable to load config")?;
     let config = Arc::new(config);
-    claims_core::tracing::init(&config.log)?;
-
-    let db = PgPoolOptions::new()
-        .max_connections(5)
-        .test_before_acquire(true)
-        .acquire_timeout(Duration::from_secs(5))
-        // This allows us to select which schema to use
-        // see: https://docs.rs/sqlx/latest/sqlx/pool/struct.PoolOptions.html#method.after_connect
-        .after_connect(|conn, _meta| {
-            Box::pin(async move {
-                // When directly invoking `Executor` methods,
-                // it is possible to execute multiple statements with one call.
-                conn.execute("SET search_path = 'claims';").await?;
-                Ok(())
-            })
-        })
-        .connect(&config.db.url)
-        .await
-        .context("Unable to connect to database")?;
+    let log_level = claims_core::tracing::init(&config.log)?;
+    let metrics = claims_core::metrics::install_prometheus()?;
+    common::metrics::describe();
+
+    let db = claims_core::postgres::connect(&config.db).await?;
 
-    sqlx::migrate!()
+    db::MIGRATOR
         .run(&db)
         .await
         .context("Unable to exec db migrations")?;
 
-    start_web_server(config, &db)
-        .await
-        .context("Unable to start web server")?;
-
-    Ok(())
-}
-
-/// Starts the web server given a config [`config::Server`]
-pub async fn start_web_server(config: Arc<AppConfig>, db: &PgPool) -> anyhow::Result<()> {
-    // Initialize context
+    // Initialize the context shared by the apis
     let context = ApiContext {
         config: config.clone(),
         db: db.clone(),
-        events: EventService::new(&config.schema_registry.url),
+        claims: ClaimService::new(
+            db.clone(),
+            EventService::new(&config.schema_registry.url),
+            Duration::from_secs(config.idempotency.ttl_secs),
+        ),
+        metrics,
     };
 
+    let mut shutdown = ShutdownCoordinator::new(Duration::from_secs(config.graceful_shutdown));
+    if let Some(grpc) = &config.grpc {
+        let port = grpc.port;
+        let context = context.clone();
+        shutdown.spawn("grpc server", move |token| async move {
+            api::grpc::start_grpc_server(context, port, token)
+                .await
+                .context("Unable to start grpc server")
+        });
+    }
+    if let Some(admin) = config.admin.clone() {
+        shutdown.spawn("admin server", move |token| async move {
+            claims_core::admin::serve(&admin, log_level, token)
+                .await
+                .context("Unable to start admin server")
+        });
+    }
+    shutdown.spawn("idempotency key purge", |token| {
+        service::idempotency::purge_expired(
+            db.clone(),
+            Duration::from_secs(config.idempotency.ttl_secs),
+            Duration::from_secs(config.idempotency.purge_interval_secs),
+            token,
+        )
+    });
+    shutdown.spawn("web server", |token| async move {
+        start_web_server(context, token)
+            .await
+            .context("Unable to start web server")
+    });
+    shutdown.on_shutdown("db pool", async move {
+        db.close().await;
+        Ok(())
+    });
+
+    let result = shutdown.run().await;
+    claims_core::otel::shutdown();
+    result
+}
+
+/// Starts the web server given a config [`config::Server`]
+pub async fn start_web_server(
+    context: ApiContext,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
+    let port = context.config.server.port;
+
     // Initialize routing
     let routing = init_routing(context);
 
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.server.port));
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
     tracing::info!("Rest server listening on {addr}");
     axum::Server::bind(&addr)
         .serve(routing.into_make_service_with_connect_info::<SocketAddr>())
-        .with_graceful_shutdown(claims_core::shutdown::shutdown_signal())
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
         .await?;
 
     Ok(())
@@ -79,12 +118,31 @@ pub async fn start_web_server(config: Arc<AppConfig>, db: &PgPool) -> anyhow::Re
 
 /// Merge all routers
 fn init_routing(context: ApiContext) -> Router {
-    let base_router = Router::new().route("/health", get(health));
+    let base_router = Router::new()
+        .route("/health", get(liveness))
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
+        .route("/metrics", get(render_metrics))
+        .layer(Extension(context.clone()))
+        // OpenAPI document and swagger ui
+        .merge(api::rest::openapi::routes())
+        // GraphQL api and graphiql
+        .merge(api::graphql::routes(context.clone()));
 
     // Initialize rest router
     let rest_router = api::rest::routing::init()
         // Add context extension
         .layer(Extension(context));
 
-    base_router.merge(rest_router)
+    let router = base_router
+        .merge(rest_router)
+        // Record http metrics per matched route
+        .route_layer(middleware::from_fn(track_metrics))
+        // Handle each request in its own (possibly remote parented) span
+        .route_layer(middleware::from_fn(trace_request))
+        // Accept or generate the correlation id of each request
+        .route_layer(middleware::from_fn(correlation_id));
+
+    // Unmatched routes and methods are problem details, like the errors of the handlers
+    problem_responses(router)
 }
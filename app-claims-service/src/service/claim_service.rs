@@ -0,0 +1,356 @@
+use crate::common::error::DbError::{Conflict, NotFound};
+use crate::common::error::{AppError, FieldError};
+use crate::common::misc::generate_random_string;
+use crate::common::validation::validate;
+use crate::db;
+use crate::db::entities::{ClaimDb, PartyDb};
+use crate::service::commands::{AddParty, CreateClaim, RemoveParty, UpdateClaim, UpdateParty};
+use crate::service::event_service::EventService;
+use crate::service::idempotency;
+use crate::service::queries::{ClaimsPage, ListClaims};
+use claims_core::kafka::event_type::EventType;
+use claims_model::model::{Claim, Party};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Claims and parties operations, independent of the api (REST, gRPC, GraphQL) calling them.
+///
+/// Each command runs in its own transaction along with its outbox events,
+/// updates apply only to the expected version of the resource.
+#[derive(Clone)]
+pub struct ClaimService {
+    db: PgPool,
+    events: EventService,
+    /// Time the idempotency keys of the commands are kept
+    key_ttl: Duration,
+}
+
+impl ClaimService {
+    pub fn new(db: PgPool, events: EventService, key_ttl: Duration) -> Self {
+        Self {
+            db,
+            events,
+            key_ttl,
+        }
+    }
+
+    pub async fn list_claims(&self, query: ListClaims) -> Result<ClaimsPage, AppError> {
+        let (offset, limit) = query.bounds();
+        let filter = query.filter();
+        let entities =
+            db::claims::fetch_page(&self.db, &filter, query.sort, query.order, offset, limit)
+                .await?;
+        let total = db::claims::count(&self.db, &filter).await?;
+        Ok(ClaimsPage {
+            claims: entities.into_iter().map(|e| e.into()).collect(),
+            offset,
+            limit,
+            total,
+        })
+    }
+
+    pub async fn fetch_claim(&self, id: i32) -> Result<Claim, AppError> {
+        let claim = db::claims::fetch_one(&self.db, id)
+            .await?
+            .ok_or(AppError::DbError(NotFound))?;
+        Ok(claim.into())
+    }
+
+    /// The claim along with its parties
+    pub async fn fetch_details(&self, id: i32) -> Result<(Claim, Vec<Party>), AppError> {
+        let claim = self.fetch_claim(id).await?;
+        let parties = db::parties::fetch_by_claim(&self.db, id).await?;
+        Ok((claim, parties.into_iter().map(|e| e.into()).collect()))
+    }
+
+    pub async fn fetch_parties(&self, claim_id: i32) -> Result<Vec<Party>, AppError> {
+        // Validate that the claim exists
+        self.fetch_claim(claim_id).await?;
+
+        let entities = db::parties::fetch_by_claim(&self.db, claim_id).await?;
+        Ok(entities.into_iter().map(|e| e.into()).collect())
+    }
+
+    /// The parties of several claims at once, claims without parties are missing
+    pub async fn fetch_parties_by_claims(
+        &self,
+        claim_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<Party>>, AppError> {
+        let entities = db::parties::fetch_by_claims(&self.db, claim_ids).await?;
+
+        let mut parties: HashMap<i32, Vec<Party>> = HashMap::new();
+        for entity in entities {
+            parties
+                .entry(entity.claim_id)
+                .or_default()
+                .push(entity.into());
+        }
+        Ok(parties)
+    }
+
+    pub async fn fetch_party(&self, claim_id: i32, party_id: i32) -> Result<Party, AppError> {
+        let party = db::parties::fetch_one_by_claim(&self.db, claim_id, party_id)
+            .await?
+            .ok_or(AppError::DbError(NotFound))?;
+        Ok(party.into())
+    }
+
+    pub async fn create_claim(&self, command: CreateClaim) -> Result<Claim, AppError> {
+        let scope = "create-claim";
+        let key = command.idempotency_key.as_deref();
+
+        let mut tx = self.db.begin().await?;
+        if let Some(claim) =
+            idempotency::replay(&mut tx, key, scope, &command, self.key_ttl).await?
+        {
+            return Ok(claim);
+        }
+
+        let claim_no = generate_random_string(10);
+        let entity = ClaimDb::new(claim_no, command.incident_type);
+        let entity = db::claims::create(&mut tx, entity).await?;
+
+        let claim: Claim = entity.into();
+        self.events
+            .send_claim(&mut tx, &claim, EventType::Created)
+            .await?;
+        idempotency::complete(&mut tx, key, scope, &claim).await?;
+
+        tx.commit().await?;
+        Ok(claim)
+    }
+
+    pub async fn update_claim(&self, command: UpdateClaim) -> Result<Claim, AppError> {
+        let mut tx = self.db.begin().await?;
+        let mut e = db::claims::fetch_one(&mut *tx, command.id)
+            .await?
+            .ok_or(AppError::DbError(NotFound))?;
+        command.expected_version.check(e.version)?;
+
+        e.status = command.status;
+        e.incident_type = command.incident_type;
+
+        // Fails if the claim was updated concurrently since read
+        let entity = db::claims::update(&mut tx, e)
+            .await?
+            .ok_or(AppError::DbError(Conflict))?;
+
+        let claim: Claim = entity.into();
+        self.events
+            .send_claim(&mut tx, &claim, EventType::Updated)
+            .await?;
+
+        tx.commit().await?;
+        Ok(claim)
+    }
+
+    pub async fn add_party(&self, command: AddParty) -> Result<Party, AppError> {
+        validate(&command)?;
+        let scope = format!("add-party/{}", command.claim_id);
+        let key = command.idempotency_key.as_deref();
+
+        let mut tx = self.db.begin().await?;
+        if let Some(party) =
+            idempotency::replay(&mut tx, key, &scope, &command, self.key_ttl).await?
+        {
+            return Ok(party);
+        }
+
+        let claim = db::claims::fetch_one(&mut *tx, command.claim_id)
+            .await?
+            .ok_or(AppError::DbError(NotFound))?;
+        let party = PartyDb::new(claim.id, command.data);
+        let party = db::parties::create(&mut tx, party).await?;
+
+        let party: Party = party.into();
+        self.events
+            .send_party(&mut tx, &party, EventType::Created)
+            .await?;
+        idempotency::complete(&mut tx, key, &scope, &party).await?;
+
+        tx.commit().await?;
+        Ok(party)
+    }
+
+    pub async fn update_party(&self, command: UpdateParty) -> Result<Party, AppError> {
+        validate(&command)?;
+
+        let mut tx = self.db.begin().await?;
+        let mut party =
+            db::parties::fetch_one_by_claim(&mut *tx, command.claim_id, command.party_id)
+                .await?
+                .ok_or(AppError::DbError(NotFound))?;
+        command.expected_version.check(party.version)?;
+
+        let data = command.data;
+        if data.r#type() != party.r#type {
+            return Err(AppError::Validation(vec![FieldError {
+                field: "data.type".into(),
+                message: format!(
+                    "must remain {}, the type of a party cannot change",
+                    party.r#type
+                ),
+            }]));
+        }
+
+        party.r#type = data.r#type();
+        party.subtype = data.subtype();
+        party.data = sqlx::types::Json(data);
+
+        // Fails if the party was updated concurrently since read
+        let party = db::parties::update(&mut tx, party)
+            .await?
+            .ok_or(AppError::DbError(Conflict))?;
+
+        let party: Party = party.into();
+        self.events
+            .send_party(&mut tx, &party, EventType::Updated)
+            .await?;
+
+        tx.commit().await?;
+        Ok(party)
+    }
+
+    pub async fn remove_party(&self, command: RemoveParty) -> Result<Party, AppError> {
+        let mut tx = self.db.begin().await?;
+        let party = db::parties::fetch_one_by_claim(&mut *tx, command.claim_id, command.party_id)
+            .await?
+            .ok_or(AppError::DbError(NotFound))?;
+        let party = db::parties::delete(&mut tx, party).await?;
+
+        let party: Party = party.into();
+        self.events
+            .send_party(&mut tx, &party, EventType::Deleted)
+            .await?;
+
+        tx.commit().await?;
+        Ok(party)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::error::DbError;
+    use crate::service::commands::ExpectedVersion;
+    use crate::service::event_service::EventEncoder;
+    use axum::async_trait;
+    use claims_model::model::proto::claim::Claim as ClaimProto;
+    use claims_model::model::proto::party::Party as PartyProto;
+    use claims_model::model::{ClaimStatus, IncidentType, PartyData, PartySubtype, Person};
+    use protobuf::Message;
+    use std::sync::Arc;
+
+    /// Encodes the plain protobuf messages, without the schema registry
+    struct PlainEncoder;
+
+    #[async_trait]
+    impl EventEncoder for PlainEncoder {
+        async fn encode_claim(&self, claim: &ClaimProto) -> anyhow::Result<Vec<u8>> {
+            Ok(claim.write_to_bytes()?)
+        }
+
+        async fn encode_party(&self, party: &PartyProto) -> anyhow::Result<Vec<u8>> {
+            Ok(party.write_to_bytes()?)
+        }
+    }
+
+    fn service(db: &PgPool) -> ClaimService {
+        let events = EventService::from_encoder(Arc::new(PlainEncoder));
+        ClaimService::new(db.clone(), events, Duration::from_secs(60))
+    }
+
+    async fn insert_claim(db: &PgPool) -> Claim {
+        let mut tx = db.begin().await.unwrap();
+        let claim = db::claims::create(&mut tx, ClaimDb::new("AB-1", IncidentType::Collision))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        claim.into()
+    }
+
+    async fn outbox_events(db: &PgPool, claim_id: i32) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM claim_outbox_event WHERE aggregateid = $1")
+            .bind(claim_id.to_string())
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn creates_an_open_claim_along_with_its_event(db: PgPool) {
+        let service = service(&db);
+
+        let claim = service
+            .create_claim(CreateClaim {
+                incident_type: IncidentType::Collision,
+                idempotency_key: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(claim.status, ClaimStatus::Open);
+        let fetched = service.fetch_claim(claim.id).await.unwrap();
+        assert_eq!(fetched.claim_no, claim.claim_no);
+        assert_eq!(outbox_events(&db, claim.id).await, 1);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn rejects_the_update_of_a_stale_version(db: PgPool) {
+        let service = service(&db);
+        let claim = insert_claim(&db).await;
+
+        let updated = service
+            .update_claim(UpdateClaim {
+                id: claim.id,
+                incident_type: IncidentType::Collision,
+                status: ClaimStatus::Closed,
+                expected_version: ExpectedVersion::OneOf(vec![claim.version - 1]),
+            })
+            .await;
+
+        assert!(matches!(updated, Err(AppError::DbError(DbError::Conflict))));
+        let unchanged = service.fetch_claim(claim.id).await.unwrap();
+        assert_eq!(unchanged.status, ClaimStatus::Open);
+        assert_eq!(unchanged.version, claim.version);
+        assert_eq!(outbox_events(&db, claim.id).await, 0);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn missing_claim_is_not_found(db: PgPool) {
+        let service = service(&db);
+        let not_found = |result: Result<(), AppError>| {
+            matches!(result, Err(AppError::DbError(DbError::NotFound)))
+        };
+
+        assert!(not_found(service.fetch_claim(42).await.map(|_| ())));
+        assert!(not_found(
+            service
+                .update_claim(UpdateClaim {
+                    id: 42,
+                    incident_type: IncidentType::Collision,
+                    status: ClaimStatus::Closed,
+                    expected_version: ExpectedVersion::Any,
+                })
+                .await
+                .map(|_| ())
+        ));
+        assert!(not_found(
+            service
+                .add_party(AddParty {
+                    claim_id: 42,
+                    data: PartyData::Person(Person {
+                        subtype: PartySubtype::Owner,
+                        name: "Jane Doe".into(),
+                    }),
+                    idempotency_key: None,
+                })
+                .await
+                .map(|_| ())
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::common::error::{AppError, DbError};
+use crate::common::validation::{join_field, Validate, ValidationErrors};
+use claims_model::model::{ClaimStatus, IncidentType, PartyData};
+use serde::Serialize;
+
+// Commands of the claim service, the requests of the apis mapped to the domain.
+// The serialized command (without its idempotency key) is the request stored with the key.
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateClaim {
+    pub incident_type: IncidentType,
+    /// A retry with the same key returns the claim created first
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UpdateClaim {
+    pub id: i32,
+    pub incident_type: IncidentType,
+    pub status: ClaimStatus,
+    pub expected_version: ExpectedVersion,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddParty {
+    #[serde(skip)]
+    pub claim_id: i32,
+    pub data: PartyData,
+    /// A retry with the same key returns the party added first
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UpdateParty {
+    pub claim_id: i32,
+    pub party_id: i32,
+    pub data: PartyData,
+    pub expected_version: ExpectedVersion,
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoveParty {
+    pub claim_id: i32,
+    pub party_id: i32,
+}
+
+impl Validate for AddParty {
+    fn validate(&self, field: &str, errors: &mut ValidationErrors) {
+        self.data.validate(&join_field(field, "data"), errors);
+    }
+}
+
+impl Validate for UpdateParty {
+    fn validate(&self, field: &str, errors: &mut ValidationErrors) {
+        self.data.validate(&join_field(field, "data"), errors);
+    }
+}
+
+/// The versions of the resource an update applies to (optimistic concurrency)
+#[derive(Clone, Debug, Default)]
+pub enum ExpectedVersion {
+    #[default]
+    Any,
+    OneOf(Vec<i32>),
+}
+
+impl ExpectedVersion {
+    /// Fails with a conflict unless the current version of the resource is expected
+    pub fn check(&self, current: i32) -> Result<(), AppError> {
+        match self {
+            ExpectedVersion::OneOf(versions) if !versions.contains(&current) => {
+                Err(AppError::DbError(DbError::Conflict))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<Option<i32>> for ExpectedVersion {
+    fn from(version: Option<i32>) -> Self {
+        match version {
+            Some(version) => ExpectedVersion::OneOf(vec![version]),
+            None => ExpectedVersion::Any,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_version_conflicts_unless_current() {
+        assert!(ExpectedVersion::Any.check(3).is_ok());
+        assert!(ExpectedVersion::from(None).check(3).is_ok());
+        assert!(ExpectedVersion::from(Some(3)).check(3).is_ok());
+        assert!(ExpectedVersion::OneOf(vec![2, 3]).check(3).is_ok());
+
+        for expected in [
+            ExpectedVersion::from(Some(2)),
+            ExpectedVersion::OneOf(vec![]),
+        ] {
+            assert!(matches!(
+                expected.check(3),
+                Err(AppError::DbError(DbError::Conflict))
+            ));
+        }
+    }
+}
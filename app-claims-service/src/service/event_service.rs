@@ -1,5 +1,11 @@
 use crate::{db::entities::ClaimOutboxEventDb, db::events::send_event, db::PostgresTx};
+use axum::async_trait;
+use claims_core::correlation::CorrelationId;
+use claims_core::kafka::event_type::EventType;
+use claims_core::otel::{self, TRACEPARENT_HEADER, TRACESTATE_HEADER};
 use claims_core::{proto_encode::encoder::ProtoEncoder, proto_encode::message::MessageKeyPair};
+use claims_model::model::proto::claim::Claim as ClaimProto;
+use claims_model::model::proto::party::Party as PartyProto;
 use claims_model::{
     model::proto::ProtoMap,
     model::{Claim, Party},
@@ -7,70 +13,112 @@ use claims_model::{
 use schema_registry_converter::{
     async_impl::easy_proto_raw::EasyProtoRawEncoder, async_impl::schema_registry::SrSettings,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Encodes the payloads of the outbox events
+#[async_trait]
+pub trait EventEncoder: Send + Sync {
+    async fn encode_claim(&self, claim: &ClaimProto) -> anyhow::Result<Vec<u8>>;
+
+    async fn encode_party(&self, party: &PartyProto) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Encodes the events with the schemas of the schema registry, keyed by claim id as string
+#[async_trait]
+impl EventEncoder for EasyProtoRawEncoder {
+    async fn encode_claim(&self, claim: &ClaimProto) -> anyhow::Result<Vec<u8>> {
+        let encoded = self
+            .encode_topic_name_raw_key(
+                "claimsdb.claim.events",
+                MessageKeyPair(claim, claim.id.to_string().as_bytes()),
+            )
+            .await?;
+        Ok(encoded.payload().into())
+    }
+
+    async fn encode_party(&self, party: &PartyProto) -> anyhow::Result<Vec<u8>> {
+        let encoded = self
+            .encode_topic_name_raw_key(
+                "claimsdb.party.events",
+                MessageKeyPair(party, party.claim_id.to_string().as_bytes()),
+            )
+            .await?;
+        Ok(encoded.payload().into())
+    }
+}
+
 #[derive(Clone)]
 pub struct EventService {
-    proto_encoder: Arc<EasyProtoRawEncoder>,
+    encoder: Arc<dyn EventEncoder>,
 }
 
 impl EventService {
     pub fn new<S: AsRef<str>>(schema_registry_url: S) -> Self {
         let settings = SrSettings::new(schema_registry_url.as_ref().into());
-        let proto_encoder = EasyProtoRawEncoder::new(settings);
-        Self {
-            proto_encoder: Arc::new(proto_encoder),
-        }
+        Self::from_encoder(Arc::new(EasyProtoRawEncoder::new(settings)))
     }
-    pub async fn send_claim(&self, tx: &mut PostgresTx<'_>, claim: &Claim) -> anyhow::Result<()> {
-        // Create the protobuf message from Claim
 
-        let proto = claim.to_proto();
+    /// Events encoded by the given encoder rather than with the schema registry
+    pub fn from_encoder(encoder: Arc<dyn EventEncoder>) -> Self {
+        Self { encoder }
+    }
 
-        // Encode the protobuf key is claim id as string
-        let encoded = self
-            .proto_encoder
-            .encode_topic_name_raw_key(
-                "claimsdb.claim.events",
-                MessageKeyPair(&proto, proto.id.to_string().as_bytes()),
-            )
-            .await?;
+    pub async fn send_claim(
+        &self,
+        tx: &mut PostgresTx<'_>,
+        claim: &Claim,
+        event_type: EventType,
+    ) -> anyhow::Result<()> {
+        // Create the protobuf message from Claim
+        let payload = self.encoder.encode_claim(&claim.to_proto()).await?;
 
         // Send the message via the outbox table
         let event = ClaimOutboxEventDb {
             aggregatetype: "claim".into(),
             aggregateid: claim.id.to_string(),
-            r#type: "update".into(),
-            payload: encoded.payload().into(),
+            r#type: event_type.to_string(),
+            payload,
             ..Default::default()
         };
-        let _ = send_event(tx, event).await?;
+        let _ = send_event(tx, with_request_context(event)).await?;
         Ok(())
     }
 
-    pub async fn send_party(&self, tx: &mut PostgresTx<'_>, party: &Party) -> anyhow::Result<()> {
-        // Create the protobuf message from Claim
-
-        let proto = party.to_proto();
-
-        // Encode the protobuf key is claim id as string
-        let encoded = self
-            .proto_encoder
-            .encode_topic_name_raw_key(
-                "claimsdb.party.events",
-                MessageKeyPair(&proto, proto.claim_id.to_string().as_bytes()),
-            )
-            .await?;
+    /// Removed parties are sent with their last state and the [`EventType::Deleted`] type.
+    /// Party events are keyed by claim id so a tombstone would compact away the other parties.
+    pub async fn send_party(
+        &self,
+        tx: &mut PostgresTx<'_>,
+        party: &Party,
+        event_type: EventType,
+    ) -> anyhow::Result<()> {
+        // Create the protobuf message from Party
+        let payload = self.encoder.encode_party(&party.to_proto()).await?;
 
         // Send the message via the outbox table
         let event = ClaimOutboxEventDb {
             aggregatetype: "party".into(),
             aggregateid: party.claim_id.to_string(), // Key is party claim_id
-            r#type: "update".into(),
-            payload: encoded.payload().into(),
+            r#type: event_type.to_string(),
+            payload,
             ..Default::default()
         };
-        let _ = send_event(tx, event).await?;
+        let _ = send_event(tx, with_request_context(event)).await?;
         Ok(())
     }
 }
+
+/// Stores the trace context of the current span and the current correlation id along with the
+/// event, debezium routes both to the kafka message headers so that consumers continue the same trace
+fn with_request_context(mut event: ClaimOutboxEventDb) -> ClaimOutboxEventDb {
+    let mut carrier: HashMap<String, String> = HashMap::new();
+    otel::inject(&tracing::Span::current(), &mut carrier);
+
+    event.traceparent = carrier.remove(TRACEPARENT_HEADER);
+    event.tracestate = carrier
+        .remove(TRACESTATE_HEADER)
+        .filter(|state| !state.is_empty());
+    event.correlation_id = CorrelationId::current().map(|id| id.to_string());
+    event
+}
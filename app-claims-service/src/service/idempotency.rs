@@ -0,0 +1,198 @@
+use crate::common::error::{AppError, IdempotencyError};
+use crate::db;
+use crate::db::PostgresTx;
+use anyhow::anyhow;
+use claims_core::shutdown::ShutdownToken;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+
+// The idempotency key of a command is reserved in the transaction of the command along with
+// the serialized command and the result is stored on success, so a retry with the same key gets
+// the original result instead of creating the resource (and its outbox events) twice.
+// A concurrent retry waits for the transaction of the first request and gets its result.
+// Keys expire after a ttl, then a retry is handled as a new request.
+
+/// Returns the stored result if the key was used before by the same command within `ttl`.
+///
+/// `scope` identifies the command (eg. `create-claim`) as keys are unique per command.
+pub async fn replay<Cmd, Res>(
+    tx: &mut PostgresTx<'_>,
+    key: Option<&str>,
+    scope: &str,
+    command: &Cmd,
+    ttl: Duration,
+) -> Result<Option<Res>, AppError>
+where
+    Cmd: Serialize,
+    Res: DeserializeOwned,
+{
+    let Some(key) = key else {
+        return Ok(None);
+    };
+    let request = serde_json::to_value(command).map_err(anyhow::Error::from)?;
+    if db::idempotency::reserve(tx, key, scope, request.clone(), ttl).await? {
+        return Ok(None);
+    }
+
+    // The key was reserved by a committed transaction, which also stored the response
+    let stored = db::idempotency::fetch_one(tx, key, scope)
+        .await?
+        .ok_or_else(|| anyhow!("Idempotency key {key} of {scope} not found once reserved"))?;
+    if stored.request.0 != request {
+        return Err(AppError::Idempotency(IdempotencyError::KeyReused));
+    }
+    let response = stored
+        .response
+        .ok_or_else(|| anyhow!("Idempotency key {key} of {scope} stored without a response"))?;
+    let response = serde_json::from_value(response.0).map_err(anyhow::Error::from)?;
+    Ok(Some(response))
+}
+
+/// Stores the result of the command that reserved the key
+pub async fn complete<Res: Serialize>(
+    tx: &mut PostgresTx<'_>,
+    key: Option<&str>,
+    scope: &str,
+    response: &Res,
+) -> Result<(), AppError> {
+    if let Some(key) = key {
+        let response = serde_json::to_value(response).map_err(anyhow::Error::from)?;
+        db::idempotency::complete(tx, key, scope, response).await?;
+    }
+    Ok(())
+}
+
+/// Deletes the keys older than `ttl` every `interval` until shutdown
+pub async fn purge_expired(
+    db: PgPool,
+    ttl: Duration,
+    interval: Duration,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = ticks.tick() => match db::idempotency::purge(&db, ttl).await {
+                Ok(purged) => tracing::debug!("Purged {} expired idempotency keys", purged),
+                // Retried on the next tick
+                Err(e) => tracing::warn!("Unable to purge expired idempotency keys: {:?}", e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    /// Runs a command with the key, returns the replayed response if any
+    async fn run(
+        db: &PgPool,
+        key: &str,
+        request: Value,
+        response: Value,
+    ) -> Result<Option<Value>, AppError> {
+        let mut tx = db.begin().await?;
+        if let Some(replayed) = replay(&mut tx, Some(key), "test", &request, TTL).await? {
+            return Ok(Some(replayed));
+        }
+        complete(&mut tx, Some(key), "test", &response).await?;
+        tx.commit().await?;
+        Ok(None)
+    }
+
+    async fn expire(db: &PgPool, key: &str) {
+        sqlx::query(
+            r#"UPDATE idempotency_key SET created_at = now() - interval '1 day' WHERE "key" = $1"#,
+        )
+        .bind(key)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn replays_the_response_of_the_same_request(db: PgPool) {
+        let first = run(&db, "k", json!({"a": 1}), json!("first")).await;
+        let retry = run(&db, "k", json!({"a": 1}), json!("retry")).await;
+
+        assert!(first.unwrap().is_none());
+        assert_eq!(retry.unwrap(), Some(json!("first")));
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn rejects_the_key_reused_with_another_request(db: PgPool) {
+        run(&db, "k", json!({"a": 1}), json!("first"))
+            .await
+            .unwrap();
+
+        let reused = run(&db, "k", json!({"a": 2}), json!("reused")).await;
+
+        assert!(matches!(
+            reused,
+            Err(AppError::Idempotency(IdempotencyError::KeyReused))
+        ));
+        assert_eq!(reused.unwrap_err().problem().status, 422);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn concurrent_request_waits_for_the_first(db: PgPool) {
+        let mut tx = db.begin().await.unwrap();
+        let reserved: Option<Value> = replay(&mut tx, Some("k"), "test", &json!({}), TTL)
+            .await
+            .unwrap();
+        assert!(reserved.is_none());
+
+        let retry = tokio::spawn({
+            let db = db.clone();
+            async move { run(&db, "k", json!({}), json!("retry")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!retry.is_finished());
+
+        complete(&mut tx, Some("k"), "test", &json!("first"))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        assert_eq!(retry.await.unwrap().unwrap(), Some(json!("first")));
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn expired_keys_are_reserved_again(db: PgPool) {
+        run(&db, "k", json!({"a": 1}), json!("first"))
+            .await
+            .unwrap();
+        expire(&db, "k").await;
+
+        let retry = run(&db, "k", json!({"a": 2}), json!("retry")).await;
+        let again = run(&db, "k", json!({"a": 2}), json!("again")).await;
+
+        assert!(retry.unwrap().is_none());
+        assert_eq!(again.unwrap(), Some(json!("retry")));
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    #[ignore = "needs the postgres of docker compose, see USAGE"]
+    async fn purges_expired_keys(db: PgPool) {
+        run(&db, "expired", json!({}), json!("")).await.unwrap();
+        run(&db, "recent", json!({}), json!("")).await.unwrap();
+        expire(&db, "expired").await;
+
+        let purged = db::idempotency::purge(&db, TTL).await.unwrap();
+
+        assert_eq!(purged, 1);
+        let replayed = run(&db, "recent", json!({}), json!("")).await.unwrap();
+        assert!(replayed.is_some());
+    }
+}
@@ -1 +1,5 @@
+pub mod claim_service;
+pub mod commands;
 pub mod event_service;
+pub mod idempotency;
+pub mod queries;
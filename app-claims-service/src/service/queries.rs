@@ -0,0 +1,93 @@
+use crate::db::claims::{ClaimFilter, ClaimSort, SortOrder};
+use claims_model::model::{Claim, ClaimStatus, IncidentType};
+
+// Queries of the claim service, the listings requested by the apis mapped to the domain.
+
+/// A page of the claims matching the optional filters
+#[derive(Clone, Debug)]
+pub struct ListClaims {
+    /// Number of claims to skip
+    pub offset: i64,
+    /// Number of claims of the page, at most [`ListClaims::MAX_LIMIT`]
+    pub limit: i64,
+    pub status: Option<ClaimStatus>,
+    pub incident_type: Option<IncidentType>,
+    /// Prefix of the claim number
+    pub claim_no: Option<String>,
+    pub sort: ClaimSort,
+    pub order: SortOrder,
+}
+
+impl Default for ListClaims {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: Self::DEFAULT_LIMIT,
+            status: None,
+            incident_type: None,
+            claim_no: None,
+            sort: ClaimSort::default(),
+            order: SortOrder::default(),
+        }
+    }
+}
+
+impl ListClaims {
+    pub const DEFAULT_LIMIT: i64 = 20;
+    pub const MAX_LIMIT: i64 = 100;
+
+    /// The requested page bounds, limited to [`ListClaims::MAX_LIMIT`] items
+    pub fn bounds(&self) -> (i64, i64) {
+        (self.offset.max(0), self.limit.clamp(1, Self::MAX_LIMIT))
+    }
+
+    pub fn filter(&self) -> ClaimFilter {
+        ClaimFilter {
+            status: self.status,
+            incident_type: self.incident_type,
+            claim_no: self.claim_no.clone().filter(|prefix| !prefix.is_empty()),
+        }
+    }
+}
+
+/// A page of the claims listing along with the total number of matching claims
+pub struct ClaimsPage {
+    pub claims: Vec<Claim>,
+    pub offset: i64,
+    pub limit: i64,
+    pub total: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ListClaims;
+
+    fn query(offset: i64, limit: i64) -> ListClaims {
+        ListClaims {
+            offset,
+            limit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bounds_are_clamped() {
+        assert_eq!(query(20, 10).bounds(), (20, 10));
+        assert_eq!(query(0, 0).bounds(), (0, 1));
+        assert_eq!(query(-5, -1).bounds(), (0, 1));
+        assert_eq!(
+            query(0, ListClaims::MAX_LIMIT + 1).bounds(),
+            (0, ListClaims::MAX_LIMIT)
+        );
+    }
+
+    #[test]
+    fn empty_claim_no_prefix_is_no_filter() {
+        let query = ListClaims {
+            claim_no: Some("".into()),
+            ..Default::default()
+        };
+
+        assert!(query.filter().claim_no.is_none());
+    }
+}
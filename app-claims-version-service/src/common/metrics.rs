@@ -0,0 +1,24 @@
+use axum::routing::get;
+use axum::Router;
+use claims_core::config::Server;
+use claims_core::metrics::PrometheusHandle;
+use claims_core::shutdown::ShutdownToken;
+use std::net::SocketAddr;
+
+/// Starts a minimal http server exposing the recorded metrics under `/metrics`
+pub async fn start_metrics_server(
+    config: &Server,
+    handle: PrometheusHandle,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
+    let router = Router::new().route("/metrics", get(move || std::future::ready(handle.render())));
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    tracing::info!("Metrics server listening on {addr}");
+    axum::Server::bind(&addr)
+        .serve(router.into_make_service())
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await?;
+
+    Ok(())
+}
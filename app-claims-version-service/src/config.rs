@@ -1,4 +1,5 @@
-use claims_core::config::{Kafka, Log, SchemaRegistry};
+use claims_core::config::validate::join_key;
+use claims_core::config::{Admin, Kafka, Log, SchemaRegistry, Server, Validate, ValidationReport};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -6,4 +7,27 @@ pub struct AppConfig {
     pub log: Log,
     pub schema_registry: SchemaRegistry,
     pub kafka: Kafka,
+    pub server: Server,
+    /// Listener of the admin endpoints, not started when missing
+    #[serde(default)]
+    pub admin: Option<Admin>,
+    /// Seconds the components are given to stop on shutdown
+    #[serde(
+        rename = "graceful-shutdown",
+        default = "claims_core::config::default_graceful_shutdown"
+    )]
+    pub graceful_shutdown: u64,
+}
+
+impl Validate for AppConfig {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        self.log.validate(&join_key(key, "log"), report);
+        self.schema_registry
+            .validate(&join_key(key, "schema_registry"), report);
+        self.kafka.validate(&join_key(key, "kafka"), report);
+        self.server.validate(&join_key(key, "server"), report);
+        if let Some(admin) = &self.admin {
+            admin.validate(&join_key(key, "admin"), report);
+        }
+    }
 }
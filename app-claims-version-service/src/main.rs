@@ -1,8 +1,14 @@
 use anyhow::Context;
-use tokio::task::JoinHandle;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::config::AppConfig;
+use claims_core::kafka::dedup::{DedupStore, InMemoryDedupStore};
+use claims_core::kafka::envelope::Envelope;
+use claims_core::kafka::event_type::EventType;
 use claims_core::kafka::proto_consumer;
+use claims_core::shutdown::{ShutdownCoordinator, ShutdownToken};
 use claims_core::tracing::init;
 use claims_model::model::proto::ProtoMap;
 use claims_model::model::{proto, Claim, Party};
@@ -12,79 +18,121 @@ mod config;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config: AppConfig =
-        claims_core::config::load("./config/application.yml").context("Unable to load config")?;
-    init(&config.log)?;
-
-    tokio::select! {
-        res = spawn_claims_consumer(&config) => {
-            if let Ok(Err(error)) = res {
-                tracing::error!("{}",error);
-            }
-        },
-        res = spawn_parties_consumer(&config) => {
-            if let Ok(Err(error)) = res {
-                tracing::error!("{}",error);
-            }
-        },
-        _ = claims_core::shutdown::shutdown_signal() => {},
+    let args = claims_core::config::Args::parse()?;
+    let layers = claims_core::config::load_layers(&args)?;
+    if args.print_config {
+        println!("{}", layers.redacted()?);
+        return Ok(());
     }
+    let config: AppConfig = layers.try_deserialize().context("Unable to load config")?;
+    let config = Arc::new(config);
+    let log_level = init(&config.log)?;
+    let metrics = claims_core::metrics::install_prometheus()?;
 
-    Ok(())
+    let mut shutdown = ShutdownCoordinator::new(Duration::from_secs(config.graceful_shutdown));
+    let server = config.clone();
+    shutdown.spawn("metrics server", |token| async move {
+        common::metrics::start_metrics_server(&server.server, metrics, token).await
+    });
+    if let Some(admin) = config.admin.clone() {
+        shutdown.spawn("admin server", move |token| async move {
+            claims_core::admin::serve(&admin, log_level, token)
+                .await
+                .context("Unable to start admin server")
+        });
+    }
+    shutdown.spawn("claims consumer", |token| {
+        run_claims_consumer(config.clone(), token)
+    });
+    shutdown.spawn("parties consumer", |token| {
+        run_parties_consumer(config.clone(), token)
+    });
+
+    let result = shutdown.run().await;
+    claims_core::otel::shutdown();
+    result
+}
+
+/// Outbox events are delivered at least once, remember the recently processed ones
+fn dedup_store() -> Arc<dyn DedupStore> {
+    let capacity = NonZeroUsize::new(10_000).expect("Non zero dedup capacity");
+    Arc::new(InMemoryDedupStore::new(
+        capacity,
+        Duration::from_secs(60 * 60),
+    ))
 }
 
 pub struct ClaimsHandler;
 
 impl ClaimsHandler {
-    pub async fn handle(&self, proto: proto::claim::Claim) -> anyhow::Result<()> {
-        let claim: Claim = Claim::from_proto(proto)?;
-        tracing::debug!("Processing claim: {:?}", claim);
+    pub async fn handle(&self, envelope: Envelope<proto::claim::Claim>) -> anyhow::Result<()> {
+        let claim: Claim = Claim::from_proto(envelope.payload)?;
+        tracing::debug!(
+            "Processing claim: {:?} (correlation id {:?})",
+            claim,
+            envelope.correlation_id
+        );
         Ok(())
     }
 }
 
-pub fn spawn_claims_consumer(config: &AppConfig) -> JoinHandle<anyhow::Result<()>> {
+pub async fn run_claims_consumer(
+    config: Arc<AppConfig>,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
     let consumer = proto_consumer::get_consumer(
         config.kafka.brokers.as_ref(),
         config.schema_registry.url.as_ref(),
         "app-claim-version",
         "claimsdb.claim.events",
-    );
+    )
+    .with_dedup(dedup_store())
+    .with_shutdown(shutdown);
 
     let handler = ClaimsHandler;
 
-    // Spawn a task to consume messages
-    tokio::spawn(async move {
-        consumer
-            .consume(|c| async { handler.handle(c).await })
-            .await
-    })
+    consumer
+        .consume_envelope(|e| async { handler.handle(e).await })
+        .await
 }
 
 pub struct PartiesHandler;
 
 impl PartiesHandler {
-    pub async fn handle(&self, proto: proto::party::Party) -> anyhow::Result<()> {
-        let party = Party::from_proto(proto)?;
-        tracing::debug!("Processing party: {:?}", party);
+    pub async fn handle(&self, envelope: Envelope<proto::party::Party>) -> anyhow::Result<()> {
+        let party = Party::from_proto(envelope.payload)?;
+        match envelope.event_type {
+            Some(EventType::Deleted) => tracing::debug!(
+                "Processing removed party: {:?} (correlation id {:?})",
+                party,
+                envelope.correlation_id
+            ),
+            _ => tracing::debug!(
+                "Processing party: {:?} (correlation id {:?})",
+                party,
+                envelope.correlation_id
+            ),
+        }
         Ok(())
     }
 }
 
-pub fn spawn_parties_consumer(config: &AppConfig) -> JoinHandle<anyhow::Result<()>> {
+pub async fn run_parties_consumer(
+    config: Arc<AppConfig>,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
     let consumer = proto_consumer::get_consumer(
         config.kafka.brokers.as_ref(),
         config.schema_registry.url.as_ref(),
         "app-claim-version",
         "claimsdb.party.events",
-    );
+    )
+    .with_dedup(dedup_store())
+    .with_shutdown(shutdown);
 
     let handler = PartiesHandler;
 
-    // Spawn a task to consume messages
-    tokio::spawn(async move {
-        consumer
-            .consume(|c| async { handler.handle(c).await })
-            .await
-    })
+    consumer
+        .consume_envelope(|e| async { handler.handle(e).await })
+        .await
 }
@@ -0,0 +1,4 @@
+// With a build script sqlx needs to be told to embed changed migrations
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
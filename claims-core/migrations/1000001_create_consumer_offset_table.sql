@@ -0,0 +1,9 @@
+-- Offsets of the consumers that keep them along with their own state (see PgOffsetStore)
+CREATE TABLE IF NOT EXISTS consumer_offset
+(
+    group_id        VARCHAR(255) NOT NULL,
+    topic           VARCHAR(255) NOT NULL,
+    "partition"     INTEGER NOT NULL,
+    "offset"        BIGINT NOT NULL,
+    PRIMARY KEY (group_id, topic, "partition")
+);
@@ -0,0 +1,8 @@
+-- Event ids already processed by the consumers that skip redelivered events (see PgDedupStore)
+CREATE TABLE IF NOT EXISTS consumer_processed_event
+(
+    consumer        VARCHAR(255) NOT NULL,
+    event_id        VARCHAR(255) NOT NULL,
+    processed_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (consumer, event_id)
+);
@@ -0,0 +1,132 @@
+//! Admin endpoints shared by the services.
+//!
+//! The routes are not authenticated, so they are served on their own listener (see [`serve`])
+//! bound to the configured [`Admin`] address, which must only be reachable inside the cluster.
+use crate::config::Admin;
+use crate::shutdown::ShutdownToken;
+use crate::tracing::LogLevelHandle;
+use axum::extract::rejection::JsonRejection;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogDirectives {
+    /// Comma separated directives in the `RUST_LOG` syntax eg. `warn,sqlx::query=debug`
+    pub directives: String,
+}
+
+/// `GET /admin/log` returns and `PUT /admin/log` replaces the log directives in effect
+pub fn routes(log_level: LogLevelHandle) -> Router {
+    Router::new()
+        .route(
+            "/admin/log",
+            get(get_log_directives).put(put_log_directives),
+        )
+        .fallback(not_found)
+        .layer(Extension(log_level))
+}
+
+/// Serves the admin [`routes`] on the configured address until shutdown
+pub async fn serve(
+    config: &Admin,
+    log_level: LogLevelHandle,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
+    let addr = SocketAddr::new(config.host, config.port);
+    tracing::info!("Admin server listening on {addr}");
+    axum::Server::bind(&addr)
+        .serve(routes(log_level).into_make_service())
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await?;
+
+    Ok(())
+}
+
+async fn get_log_directives(
+    Extension(log_level): Extension<LogLevelHandle>,
+) -> Result<Json<LogDirectives>, AdminError> {
+    let directives = log_level.current().map_err(AdminError::internal)?;
+    Ok(Json(LogDirectives { directives }))
+}
+
+async fn put_log_directives(
+    Extension(log_level): Extension<LogLevelHandle>,
+    body: Result<Json<LogDirectives>, JsonRejection>,
+) -> Result<Json<LogDirectives>, AdminError> {
+    let Json(body) = body.map_err(|r| AdminError(r.status(), r.body_text()))?;
+    let filter = LogLevelHandle::parse(&body.directives)
+        .map_err(|e| AdminError(StatusCode::BAD_REQUEST, format!("{e:#}")))?;
+    log_level.reload(filter).map_err(AdminError::internal)?;
+
+    let directives = log_level.current().map_err(AdminError::internal)?;
+    tracing::warn!("Log directives changed to {}", directives);
+    Ok(Json(LogDirectives { directives }))
+}
+
+async fn not_found() -> AdminError {
+    AdminError(StatusCode::NOT_FOUND, "No such admin endpoint".into())
+}
+
+/// Error of the admin endpoints, rendered as RFC 7807 problem details like the service apis
+#[derive(Debug)]
+struct AdminError(StatusCode, String);
+
+impl AdminError {
+    fn internal(e: anyhow::Error) -> Self {
+        AdminError(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"))
+    }
+}
+
+#[derive(Serialize)]
+struct Problem<'a> {
+    r#type: &'a str,
+    title: &'a str,
+    status: u16,
+    detail: &'a str,
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        let AdminError(status, detail) = self;
+        let problem = Problem {
+            r#type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            detail: &detail,
+        };
+        (
+            status,
+            [(CONTENT_TYPE, "application/problem+json")],
+            Json(problem),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdminError;
+    use axum::body::HttpBody;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    #[tokio::test]
+    async fn errors_are_problem_details() {
+        let response =
+            AdminError(StatusCode::BAD_REQUEST, "Invalid log directives".into()).into_response();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/problem+json");
+        let body = response.into_body().data().await.unwrap().unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["title"], "Bad Request");
+        assert_eq!(problem["detail"], "Invalid log directives");
+    }
+}
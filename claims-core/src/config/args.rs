@@ -0,0 +1,86 @@
+use anyhow::{bail, Context};
+
+const USAGE: &str = "[--config <path>] [--profile <name>] [print-config]";
+
+/// Command line arguments of the services
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    /// Base config file, `--config <path>`
+    pub config: String,
+    /// Profile whose `application-{profile}.yml` overrides the base config,
+    /// `--profile <name>` or the [`Args::PROFILE_ENV`] env var
+    pub profile: Option<String>,
+    /// `print-config` prints the effective config (with secrets redacted) instead of starting
+    pub print_config: bool,
+}
+
+impl Args {
+    pub const DEFAULT_CONFIG: &'static str = "./config/application.yml";
+    pub const PROFILE_ENV: &'static str = "APP_PROFILE";
+
+    /// Parses the arguments of the process
+    pub fn parse() -> anyhow::Result<Self> {
+        let profile = std::env::var(Self::PROFILE_ENV).ok();
+        Self::parse_from(std::env::args().skip(1), profile)
+    }
+
+    pub fn parse_from<I>(args: I, profile: Option<String>) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parsed = Self {
+            config: Self::DEFAULT_CONFIG.into(),
+            profile,
+            print_config: false,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    parsed.config = args
+                        .next()
+                        .context(format!("Missing --config value, usage: {USAGE}"))?
+                }
+                "--profile" => {
+                    parsed.profile = Some(
+                        args.next()
+                            .context(format!("Missing --profile value, usage: {USAGE}"))?,
+                    )
+                }
+                "print-config" => parsed.print_config = true,
+                other => bail!("Unknown argument {other}, usage: {USAGE}"),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_arguments_over_defaults() {
+        let parsed = Args::parse_from(
+            args(&["--config", "app.yml", "--profile", "dev", "print-config"]),
+            Some("prod".into()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed,
+            Args {
+                config: "app.yml".into(),
+                profile: Some("dev".into()),
+                print_config: true,
+            }
+        );
+        assert!(Args::parse_from(args(&["--config"]), None).is_err());
+        assert!(Args::parse_from(args(&["--unknown"]), None).is_err());
+    }
+}
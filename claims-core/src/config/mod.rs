@@ -0,0 +1,195 @@
+use crate::config::loader::redact_url_password;
+use serde::Deserialize;
+use std::fmt::{Debug, Formatter};
+use std::net::{IpAddr, Ipv4Addr};
+
+pub mod args;
+pub mod loader;
+pub mod validate;
+
+pub use self::args::Args;
+pub use self::loader::{load_layers, ConfigLayers};
+pub use self::validate::{Validate, ValidationReport};
+
+#[derive(Debug, Deserialize)]
+pub struct Log {
+    pub level: LogLevel,
+    /// Format of the standard output logs
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Additional output to rolling log files, disabled if not configured
+    pub file: Option<LogFile>,
+    pub otel: Option<Otel>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Multi line human readable output, for local development
+    Pretty,
+    /// Single line human readable output
+    Compact,
+    #[default]
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogFile {
+    pub directory: String,
+    /// File name prefix, suffixed by the date of the rotation period
+    pub prefix: String,
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// Format of the file logs, defaults to json
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogLevel {
+    pub root: Option<String>,
+    pub directives: Vec<LogDirective>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct LogDirective {
+    pub namespace: String,
+    pub level: String,
+}
+
+/// OpenTelemetry export of the tracing spans, disabled if not configured
+#[derive(Debug, Deserialize)]
+pub struct Otel {
+    /// Reported as the `service.name` resource of all spans
+    pub service: String,
+    pub exporter: OtelExporter,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OtelExporter {
+    /// Batch export to an OTLP (grpc) collector eg. `http://localhost:4317`
+    Otlp { endpoint: String },
+    /// Spans as json lines to the standard output
+    Stdout,
+    /// Spans as json lines appended to a file
+    File { path: String },
+}
+
+#[derive(Deserialize)]
+pub struct Database {
+    pub url: String,
+    #[serde(default)]
+    pub pool: DatabasePool,
+    /// Schema set as the `search_path` of every connection, the server default if not set
+    pub schema: Option<String>,
+    /// `statement_timeout` of every connection in milliseconds, no timeout if not set
+    pub statement_timeout_ms: Option<u64>,
+    /// TLS mode, the one of the url (or `prefer`) if not set
+    pub ssl_mode: Option<DatabaseSslMode>,
+    /// CA certificate verifying the server in `verify-ca` and `verify-full` modes
+    pub ssl_root_cert: Option<String>,
+}
+
+impl Debug for Database {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Database")
+            .field("url", &redact_url_password(&self.url))
+            .field("pool", &self.pool)
+            .field("schema", &self.schema)
+            .field("statement_timeout_ms", &self.statement_timeout_ms)
+            .field("ssl_mode", &self.ssl_mode)
+            .field("ssl_root_cert", &self.ssl_root_cert)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DatabasePool {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// Maximum time to wait for a free connection
+    pub acquire_timeout_secs: u64,
+    /// Idle connections above `min_connections` are closed after this time, never if not set
+    pub idle_timeout_secs: Option<u64>,
+    /// Connections are recycled after this time, never if not set
+    pub max_lifetime_secs: Option<u64>,
+}
+
+impl Default for DatabasePool {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 5,
+            idle_timeout_secs: Some(10 * 60),
+            max_lifetime_secs: Some(30 * 60),
+        }
+    }
+}
+
+/// See: https://www.postgresql.org/docs/current/libpq-ssl.html#LIBPQ-SSL-SSLMODE-STATEMENTS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DatabaseSslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Server {
+    pub port: u16,
+}
+
+/// Listener of the admin endpoints (see `claims_core::admin`), apart from the public api
+#[derive(Clone, Debug, Deserialize)]
+pub struct Admin {
+    /// Address to bind, only local connections are accepted by default
+    #[serde(default = "default_admin_host")]
+    pub host: IpAddr,
+    pub port: u16,
+}
+
+fn default_admin_host() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+#[derive(Deserialize)]
+pub struct SchemaRegistry {
+    pub url: String,
+}
+
+impl Debug for SchemaRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaRegistry")
+            .field("url", &redact_url_password(&self.url))
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Kafka {
+    pub brokers: String,
+}
+
+/// Default seconds the components of a service are given to stop on shutdown
+/// (see [`ShutdownCoordinator`](crate::shutdown::ShutdownCoordinator))
+pub fn default_graceful_shutdown() -> u64 {
+    5
+}
@@ -0,0 +1,217 @@
+use crate::config::{
+    Admin, Database, DatabaseSslMode, Kafka, Log, LogLevel, Otel, OtelExporter, SchemaRegistry,
+    Server,
+};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use tracing_subscriber::filter::LevelFilter;
+
+/// Startup validation of a configuration section
+pub trait Validate {
+    /// Adds to the report the invalid keys of the section found under `key`
+    fn validate(&self, key: &str, report: &mut ValidationReport);
+}
+
+/// Invalid keys of a configuration, collected so that all of them are reported at once
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    errors: Vec<(String, String)>,
+}
+
+impl ValidationReport {
+    pub fn error<M: Into<String>>(&mut self, key: String, message: M) {
+        self.errors.push((key, message.into()));
+    }
+
+    /// Reports the key with the message unless valid
+    pub fn check<M: Into<String>>(&mut self, valid: bool, key: String, message: M) {
+        if !valid {
+            self.error(key, message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for (key, message) in &self.errors {
+            write!(f, "\n  - {key}: {message}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Path of the `field` of the section under `key`
+pub fn join_key(key: &str, field: &str) -> String {
+    if key.is_empty() {
+        field.into()
+    } else {
+        format!("{key}.{field}")
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+impl Validate for Log {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        self.level.validate(&join_key(key, "level"), report);
+
+        if let Some(file) = &self.file {
+            let file_key = join_key(key, "file");
+            report.check(
+                !file.directory.is_empty(),
+                join_key(&file_key, "directory"),
+                "must not be empty",
+            );
+            report.check(
+                !file.prefix.is_empty(),
+                join_key(&file_key, "prefix"),
+                "must not be empty",
+            );
+        }
+
+        if let Some(otel) = &self.otel {
+            otel.validate(&join_key(key, "otel"), report);
+        }
+    }
+}
+
+impl Validate for LogLevel {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        let valid_level = |level: &str| LevelFilter::from_str(level).is_ok();
+        let message = "must be one of off, error, warn, info, debug, trace";
+
+        if let Some(root) = &self.root {
+            report.check(valid_level(root), join_key(key, "root"), message);
+        }
+        for (i, directive) in self.directives.iter().enumerate() {
+            let directive_key = join_key(key, &format!("directives[{i}]"));
+            report.check(
+                !directive.namespace.is_empty(),
+                join_key(&directive_key, "namespace"),
+                "must not be empty",
+            );
+            report.check(
+                valid_level(&directive.level),
+                join_key(&directive_key, "level"),
+                message,
+            );
+        }
+    }
+}
+
+impl Validate for Otel {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            !self.service.is_empty(),
+            join_key(key, "service"),
+            "must not be empty",
+        );
+        match &self.exporter {
+            OtelExporter::Otlp { endpoint } => report.check(
+                is_http_url(endpoint),
+                join_key(key, "exporter.endpoint"),
+                "must be an http(s) url",
+            ),
+            OtelExporter::File { path } => report.check(
+                !path.is_empty(),
+                join_key(key, "exporter.path"),
+                "must not be empty",
+            ),
+            OtelExporter::Stdout => {}
+        }
+    }
+}
+
+impl Validate for Database {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            self.url.starts_with("postgres://") || self.url.starts_with("postgresql://"),
+            join_key(key, "url"),
+            "must be a postgres:// url",
+        );
+        report.check(
+            self.schema.as_ref().map_or(true, |s| !s.is_empty()),
+            join_key(key, "schema"),
+            "must not be empty",
+        );
+        report.check(
+            self.statement_timeout_ms != Some(0),
+            join_key(key, "statement_timeout_ms"),
+            "must be greater than 0, omit it to disable the timeout",
+        );
+
+        let verify = matches!(
+            self.ssl_mode,
+            Some(DatabaseSslMode::VerifyCa | DatabaseSslMode::VerifyFull)
+        );
+        report.check(
+            !verify || self.ssl_root_cert.is_some(),
+            join_key(key, "ssl_root_cert"),
+            "is required by the verify-ca and verify-full ssl modes",
+        );
+
+        let pool = join_key(key, "pool");
+        report.check(
+            self.pool.max_connections > 0,
+            join_key(&pool, "max_connections"),
+            "must be greater than 0",
+        );
+        report.check(
+            self.pool.min_connections <= self.pool.max_connections,
+            join_key(&pool, "min_connections"),
+            "must not exceed max_connections",
+        );
+        report.check(
+            self.pool.acquire_timeout_secs > 0,
+            join_key(&pool, "acquire_timeout_secs"),
+            "must be greater than 0",
+        );
+    }
+}
+
+impl Validate for Server {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            self.port > 0,
+            join_key(key, "port"),
+            "must be greater than 0",
+        );
+    }
+}
+
+impl Validate for Admin {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            self.port > 0,
+            join_key(key, "port"),
+            "must be greater than 0",
+        );
+    }
+}
+
+impl Validate for SchemaRegistry {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            is_http_url(&self.url),
+            join_key(key, "url"),
+            "must be an http(s) url",
+        );
+    }
+}
+
+impl Validate for Kafka {
+    fn validate(&self, key: &str, report: &mut ValidationReport) {
+        report.check(
+            !self.brokers.trim().is_empty(),
+            join_key(key, "brokers"),
+            "must not be empty",
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Correlation id of a user action, followed from the REST request to the outbox events
+//! and the consumers handling them.
+//!
+//! The id of the action in progress is kept in a task local (see [`CorrelationId::scope`]),
+//! so that it is available to any code running on behalf of the action without passing it around.
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+
+tokio::task_local! {
+    static CURRENT: CorrelationId;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    /// Http header carrying the correlation id of a request (and its response)
+    pub const HTTP_HEADER: &'static str = "x-correlation-id";
+    /// Kafka header where the debezium outbox event router places the correlation id
+    pub const MESSAGE_HEADER: &'static str = "correlation_id";
+    /// Maximum length of an accepted correlation id (see the `correlation_id` outbox column)
+    pub const MAX_LEN: usize = 255;
+
+    /// Generates a new random correlation id
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Accepts a correlation id given by a client, if it is non empty, printable ascii
+    /// and at most [`CorrelationId::MAX_LEN`] characters
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let valid = !value.is_empty()
+            && value.len() <= Self::MAX_LEN
+            && value.chars().all(|c| c.is_ascii_graphic());
+        valid.then(|| Self(value.to_owned()))
+    }
+
+    /// The correlation id of the action the current task runs on behalf of, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Runs the future with this as the [`CorrelationId::current`] correlation id
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        CURRENT.scope(self, f).await
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rejects_invalid_ids() {
+        assert_eq!(
+            CorrelationId::parse(" abc-123 ").unwrap().as_str(),
+            "abc-123"
+        );
+        assert!(CorrelationId::parse("").is_none());
+        assert!(CorrelationId::parse("with space").is_none());
+        assert!(CorrelationId::parse(&"a".repeat(CorrelationId::MAX_LEN + 1)).is_none());
+    }
+
+    #[tokio::test]
+    async fn current_is_set_only_within_scope() {
+        let id = CorrelationId::generate();
+        let current = id.clone().scope(async { CorrelationId::current() }).await;
+
+        assert_eq!(current, Some(id));
+        assert_eq!(CorrelationId::current(), None);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::kafka::offset_store::OffsetStore;
+use crate::metrics::CONSUMER_LAG;
+use anyhow::Context;
+use rdkafka::client::NativeClient;
+use rdkafka::consumer::{ConsumerContext, DefaultConsumerContext};
+use rdkafka::types::RDKafkaRespErr;
+use rdkafka::{ClientContext, Offset, Statistics, TopicPartitionList};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::sync::Notify;
+
+/// Consumer context used by [`ProtoConsumer`](crate::kafka::proto_consumer::ProtoConsumer)
+///
+/// Starts the newly assigned partitions at the offsets of the [`OffsetStore`] (if any),
+/// keeps track of the revoked partitions and publishes the per partition consumer lag
+/// reported by rdkafka statistics.
+///
+/// With an offset store the consumer must be polled from a multi thread tokio runtime,
+/// as the offsets are loaded by blocking the rebalance callback in place. On a current thread
+/// runtime (eg. the default of `#[tokio::test]`) the assignment fails instead.
+#[derive(Default)]
+pub struct ProtoConsumerContext {
+    offset_store: OnceLock<Arc<dyn OffsetStore>>,
+    revoked: Mutex<Vec<(String, i32)>>,
+    error: Mutex<Option<anyhow::Error>>,
+    failure: Notify,
+}
+
+impl ProtoConsumerContext {
+    /// Assigns the partitions at the offsets of the store rather than the committed ones.
+    ///
+    /// The offsets are loaded while the rebalance is served, so the consumer must be polled
+    /// from a multi thread tokio runtime.
+    pub fn set_offset_store(&self, store: Arc<dyn OffsetStore>) -> anyhow::Result<()> {
+        self.offset_store
+            .set(store)
+            .map_err(|_| anyhow::anyhow!("Offset store already set"))
+    }
+
+    /// Returns the partitions revoked since the last call
+    pub fn take_revoked(&self) -> Vec<(String, i32)> {
+        let mut revoked = self.revoked.lock().expect("Poisoned context lock");
+        std::mem::take(&mut *revoked)
+    }
+
+    /// Returns the error of the last rebalance (if any), after which consuming must stop
+    pub fn take_error(&self) -> Option<anyhow::Error> {
+        self.error.lock().expect("Poisoned context lock").take()
+    }
+
+    /// Waits until a rebalance fails and returns its error
+    pub async fn failed(&self) -> anyhow::Error {
+        loop {
+            if let Some(e) = self.take_error() {
+                return e;
+            }
+            self.failure.notified().await;
+        }
+    }
+
+    /// Sets the stored offsets as the positions of the partitions about to be assigned,
+    /// partitions without a stored offset start from the committed one.
+    ///
+    /// The partitions whose offset could not be loaded are left out of the assignment,
+    /// so that none of them is consumed from the committed offset instead.
+    fn position(&self, tpl: &mut TopicPartitionList) -> anyhow::Result<()> {
+        let Some(store) = self.offset_store.get() else {
+            return Ok(());
+        };
+
+        let mut positioned = TopicPartitionList::new();
+        let mut error = None;
+        for element in tpl.elements() {
+            let (topic, partition) = (element.topic(), element.partition());
+            match load(store.as_ref(), topic, partition) {
+                Ok(Some(stored)) => {
+                    tracing::debug!(
+                        "Assigning {}[{}] at stored offset {}",
+                        topic,
+                        partition,
+                        stored
+                    );
+                    positioned.add_partition_offset(topic, partition, Offset::Offset(stored))?;
+                }
+                Ok(None) => positioned.add_partition_offset(topic, partition, element.offset())?,
+                Err(e) => {
+                    tracing::error!("Not assigning {}[{}]: {:?}", topic, partition, e);
+                    error.get_or_insert(e);
+                }
+            }
+        }
+
+        *tpl = positioned;
+        error.map_or(Ok(()), Err)
+    }
+}
+
+/// Loads the stored offset of the partition, blocking the rebalance callback in place
+fn load(store: &dyn OffsetStore, topic: &str, partition: i32) -> anyhow::Result<Option<i64>> {
+    let handle =
+        Handle::try_current().context("Loading the stored offsets needs a tokio runtime")?;
+    if handle.runtime_flavor() != RuntimeFlavor::MultiThread {
+        anyhow::bail!("Loading the stored offsets needs a multi thread tokio runtime");
+    }
+
+    tokio::task::block_in_place(|| handle.block_on(store.load(topic, partition)))
+        .with_context(|| format!("Unable to load the stored offset of {topic}[{partition}]"))
+}
+
+impl ClientContext for ProtoConsumerContext {
+    fn stats(&self, statistics: Statistics) {
+        for (topic, t) in statistics.topics {
+            for (partition, p) in t.partitions {
+                // Skip the internal unassigned partition and partitions with unknown lag
+                if partition < 0 || p.consumer_lag < 0 {
+                    continue;
+                }
+                metrics::gauge!(
+                    CONSUMER_LAG,
+                    p.consumer_lag as f64,
+                    "topic" => topic.clone(),
+                    "partition" => partition.to_string()
+                );
+            }
+        }
+    }
+}
+
+impl ConsumerContext for ProtoConsumerContext {
+    fn rebalance(
+        &self,
+        native_client: &NativeClient,
+        err: RDKafkaRespErr,
+        tpl: &mut TopicPartitionList,
+    ) {
+        match err {
+            RDKafkaRespErr::RD_KAFKA_RESP_ERR__ASSIGN_PARTITIONS => {
+                // Position the partitions before they are assigned, so that nothing is fetched
+                // from the committed offsets in the meantime
+                if let Err(e) = self.position(tpl) {
+                    *self.error.lock().expect("Poisoned context lock") = Some(e);
+                    self.failure.notify_one();
+                }
+                for e in tpl.elements() {
+                    tracing::debug!("Assigned partition {}[{}]", e.topic(), e.partition());
+                }
+            }
+            RDKafkaRespErr::RD_KAFKA_RESP_ERR__REVOKE_PARTITIONS => {
+                let mut revoked = self.revoked.lock().expect("Poisoned context lock");
+                for e in tpl.elements() {
+                    tracing::debug!("Revoked partition {}[{}]", e.topic(), e.partition());
+                    revoked.push((e.topic().to_owned(), e.partition()));
+                }
+            }
+            _ => tracing::warn!("Rebalance error: {:?}", err),
+        }
+
+        // Assign or revoke the partitions as rdkafka does by default
+        DefaultConsumerContext.rebalance(native_client, err, tpl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProtoConsumerContext;
+    use crate::kafka::offset_store::OffsetStore;
+    use async_trait::async_trait;
+    use rdkafka::{Offset, TopicPartitionList};
+    use std::sync::Arc;
+
+    struct StaticOffsetStore;
+
+    #[async_trait]
+    impl OffsetStore for StaticOffsetStore {
+        async fn load(&self, _topic: &str, partition: i32) -> anyhow::Result<Option<i64>> {
+            match partition {
+                0 => Ok(Some(42)),
+                2 => Err(anyhow::anyhow!("Connection refused")),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    fn offset(tpl: &TopicPartitionList, partition: i32) -> Offset {
+        tpl.find_partition("claims", partition).unwrap().offset()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn assigns_at_the_stored_offsets() {
+        let context = ProtoConsumerContext::default();
+        context
+            .set_offset_store(Arc::new(StaticOffsetStore))
+            .unwrap();
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition("claims", 0);
+        tpl.add_partition("claims", 1);
+        context.position(&mut tpl).unwrap();
+
+        assert_eq!(offset(&tpl, 0), Offset::Offset(42));
+        // Without a stored offset the partition resumes from the committed one
+        assert_eq!(offset(&tpl, 1), Offset::Invalid);
+    }
+
+    #[test]
+    fn assigns_at_the_committed_offsets_without_a_store() {
+        let context = ProtoConsumerContext::default();
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition("claims", 0);
+        context.position(&mut tpl).unwrap();
+
+        assert_eq!(offset(&tpl, 0), Offset::Invalid);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn leaves_out_the_partitions_whose_offset_failed_to_load() {
+        let context = ProtoConsumerContext::default();
+        context
+            .set_offset_store(Arc::new(StaticOffsetStore))
+            .unwrap();
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition("claims", 0);
+        tpl.add_partition("claims", 2);
+
+        assert!(context.position(&mut tpl).is_err());
+        assert_eq!(offset(&tpl, 0), Offset::Offset(42));
+        assert!(tpl.find_partition("claims", 2).is_none());
+    }
+
+    #[tokio::test]
+    async fn fails_to_assign_on_a_current_thread_runtime() {
+        let context = ProtoConsumerContext::default();
+        context
+            .set_offset_store(Arc::new(StaticOffsetStore))
+            .unwrap();
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition("claims", 0);
+
+        assert!(context.position(&mut tpl).is_err());
+        assert_eq!(tpl.count(), 0);
+    }
+}
@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use lru::LruCache;
+use rdkafka::message::Headers;
+use rdkafka::Message;
+use std::fmt::{Display, Formatter};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "postgres")]
+pub use self::postgres::PgDedupStore;
+
+/// Identity of a logical event, used to detect redelivered messages
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EventId(String);
+
+impl EventId {
+    /// Header where the debezium outbox event router places the id of the outbox row
+    pub const HEADER: &'static str = "id";
+
+    /// Extracts the event id from the [`EventId::HEADER`] header of the message.
+    ///
+    /// Messages without such header are identified by their key and position instead.
+    pub fn from_message<M: Message>(message: &M) -> Self {
+        let header = message.headers().and_then(|headers| {
+            headers
+                .iter()
+                .find(|h| h.key == Self::HEADER)
+                .and_then(|h| h.value)
+                .and_then(|v| std::str::from_utf8(v).ok())
+                .map(|v| v.trim_matches('"').to_owned())
+        });
+
+        match header {
+            Some(id) => Self(id),
+            None => {
+                let key = message
+                    .key()
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                Self(format!(
+                    "{}:{}[{}]@{}",
+                    key,
+                    message.topic(),
+                    message.partition(),
+                    message.offset()
+                ))
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for EventId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+impl From<&str> for EventId {
+    fn from(id: &str) -> Self {
+        Self(id.into())
+    }
+}
+
+impl Display for EventId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Storage of already processed event ids
+///
+/// An event is marked as processed after its handler succeeded, not in the same transaction
+/// as the side effects of the handler. A crash in between processes the event again once
+/// redelivered, so handlers must still tolerate duplicates (dedup only makes them rare).
+/// Marking first instead would lose the event whenever the handler fails after the mark.
+#[async_trait]
+pub trait DedupStore: Send + Sync {
+    /// Returns true if the event has already been processed
+    async fn contains(&self, id: &EventId) -> anyhow::Result<bool>;
+
+    /// Marks the event as processed
+    async fn insert(&self, id: &EventId) -> anyhow::Result<()>;
+}
+
+/// Bounded in memory [`DedupStore`] that forgets ids after `ttl`
+/// or when more than `capacity` ids are kept (least recently used first).
+pub struct InMemoryDedupStore {
+    entries: Mutex<LruCache<EventId, Instant>>,
+    ttl: Duration,
+}
+
+impl InMemoryDedupStore {
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            ttl,
+        }
+    }
+}
+
+#[async_trait]
+impl DedupStore for InMemoryDedupStore {
+    async fn contains(&self, id: &EventId) -> anyhow::Result<bool> {
+        let mut entries = self.entries.lock().expect("Poisoned dedup store lock");
+        let expired = match entries.get(id) {
+            Some(inserted) => inserted.elapsed() >= self.ttl,
+            None => return Ok(false),
+        };
+
+        if expired {
+            entries.pop(id);
+        }
+        Ok(!expired)
+    }
+
+    async fn insert(&self, id: &EventId) -> anyhow::Result<()> {
+        let mut entries = self.entries.lock().expect("Poisoned dedup store lock");
+        entries.put(id.clone(), Instant::now());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::{DedupStore, EventId};
+    use async_trait::async_trait;
+    use sqlx::PgPool;
+    use std::time::Duration;
+
+    /// [`DedupStore`] that keeps processed event ids per consumer in the `consumer_processed_event` table
+    ///
+    /// The table is created by the claims-core migrations (see [`crate::postgres::migrate`]).
+    #[derive(Clone)]
+    pub struct PgDedupStore {
+        pool: PgPool,
+        consumer: String,
+    }
+
+    impl PgDedupStore {
+        pub fn new<S: AsRef<str>>(pool: PgPool, consumer: S) -> Self {
+            Self {
+                pool,
+                consumer: consumer.as_ref().into(),
+            }
+        }
+
+        /// Removes the ids processed more than `retention` ago, returns the number of removed ids
+        pub async fn purge(&self, retention: Duration) -> anyhow::Result<u64> {
+            let result = sqlx::query(
+                r#"DELETE FROM consumer_processed_event
+                WHERE consumer = $1 AND processed_at < now() - make_interval(secs => $2)"#,
+            )
+            .bind(&self.consumer)
+            .bind(retention.as_secs_f64())
+            .execute(&self.pool)
+            .await?;
+            Ok(result.rows_affected())
+        }
+    }
+
+    #[async_trait]
+    impl DedupStore for PgDedupStore {
+        async fn contains(&self, id: &EventId) -> anyhow::Result<bool> {
+            let row: Option<(i32,)> = sqlx::query_as(
+                r#"SELECT 1 FROM consumer_processed_event WHERE consumer = $1 AND event_id = $2"#,
+            )
+            .bind(&self.consumer)
+            .bind(id.as_str())
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.is_some())
+        }
+
+        async fn insert(&self, id: &EventId) -> anyhow::Result<()> {
+            sqlx::query(
+                r#"INSERT INTO consumer_processed_event (consumer, event_id) VALUES ($1, $2)
+                ON CONFLICT (consumer, event_id) DO NOTHING"#,
+            )
+            .bind(&self.consumer)
+            .bind(id.as_str())
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DedupStore, EventId, InMemoryDedupStore};
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn in_memory_store_evicts_least_recently_used() {
+        let store = InMemoryDedupStore::new(NonZeroUsize::new(2).unwrap(), Duration::from_secs(60));
+        let (a, b, c) = (EventId::from("a"), EventId::from("b"), EventId::from("c"));
+
+        store.insert(&a).await.unwrap();
+        store.insert(&b).await.unwrap();
+        store.insert(&c).await.unwrap();
+
+        assert!(!store.contains(&a).await.unwrap());
+        assert!(store.contains(&b).await.unwrap());
+        assert!(store.contains(&c).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn in_memory_store_expires_entries() {
+        let store =
+            InMemoryDedupStore::new(NonZeroUsize::new(10).unwrap(), Duration::from_millis(10));
+        let id = EventId::from("a");
+
+        store.insert(&id).await.unwrap();
+        assert!(store.contains(&id).await.unwrap());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!store.contains(&id).await.unwrap());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::correlation::CorrelationId;
+use crate::kafka::dedup::EventId;
+use crate::kafka::event_type::{EventType, EVENT_TYPE_HEADER};
+use crate::kafka::offset_store::ConsumedOffset;
+use rdkafka::message::Headers;
+use rdkafka::Message;
+
+/// A decoded message along with the metadata of the event it carries
+#[derive(Debug)]
+pub struct Envelope<M> {
+    pub payload: M,
+    pub event_id: EventId,
+    /// Correlation id of the user action that produced the event, if known
+    pub correlation_id: Option<CorrelationId>,
+    /// Operation that produced the event, from the [`EVENT_TYPE_HEADER`] header
+    pub event_type: Option<EventType>,
+    pub offset: ConsumedOffset,
+}
+
+impl<M> Envelope<M> {
+    pub fn new<K: Message>(message: &K, payload: M) -> Self {
+        Self {
+            payload,
+            event_id: EventId::from_message(message),
+            correlation_id: correlation_id(message),
+            event_type: header(message, EVENT_TYPE_HEADER).and_then(|v| v.parse().ok()),
+            offset: ConsumedOffset::from_message(message),
+        }
+    }
+}
+
+/// Reads the [`CorrelationId::MESSAGE_HEADER`] header of the message
+pub fn correlation_id<K: Message>(message: &K) -> Option<CorrelationId> {
+    header(message, CorrelationId::MESSAGE_HEADER).and_then(CorrelationId::parse)
+}
+
+/// Reads a string header of the message, debezium may json encode the value so quotes are trimmed
+fn header<'a, K: Message>(message: &'a K, key: &str) -> Option<&'a str> {
+    message.headers().and_then(|headers| {
+        headers
+            .iter()
+            .find(|h| h.key == key)
+            .and_then(|h| h.value)
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .map(|v| v.trim_matches('"'))
+    })
+}
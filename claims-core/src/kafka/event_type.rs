@@ -0,0 +1,58 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Header carrying the type of the outbox event, see the connector additional placement
+pub const EVENT_TYPE_HEADER: &str = "type";
+
+/// Operation on the aggregate that produced an event
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventType {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::Created => "created",
+            EventType::Updated => "updated",
+            EventType::Deleted => "deleted",
+        }
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(EventType::Created),
+            "updated" => Ok(EventType::Updated),
+            "deleted" => Ok(EventType::Deleted),
+            other => anyhow::bail!("Unknown event type {other}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_header_values() {
+        for event_type in [EventType::Created, EventType::Updated, EventType::Deleted] {
+            assert_eq!(
+                event_type.as_str().parse::<EventType>().unwrap(),
+                event_type
+            );
+        }
+        assert!("update".parse::<EventType>().is_err());
+    }
+}
@@ -1,2 +1,9 @@
+pub mod context;
+pub mod dedup;
+pub mod envelope;
+pub mod event_type;
+pub mod offset_store;
+pub mod partition_queue;
 pub mod proto_consumer;
 pub mod proto_producer;
+pub mod trace_context;
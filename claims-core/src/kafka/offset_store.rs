@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use rdkafka::Message;
+
+#[cfg(feature = "postgres")]
+pub use self::postgres::PgOffsetStore;
+
+/// Position of a consumed message in a topic partition
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsumedOffset {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+impl ConsumedOffset {
+    pub fn from_message<M: Message>(message: &M) -> Self {
+        Self {
+            topic: message.topic().into(),
+            partition: message.partition(),
+            offset: message.offset(),
+        }
+    }
+
+    /// The offset to resume consuming from once this message is processed
+    #[inline]
+    pub fn next(&self) -> i64 {
+        self.offset + 1
+    }
+}
+
+/// Storage of consumed offsets outside of kafka
+///
+/// Allows consumers to keep their offsets next to the state they produce (eg. a projection
+/// table) so that both are always updated together.
+#[async_trait]
+pub trait OffsetStore: Send + Sync {
+    /// Returns the next offset to consume for a topic partition, if any has been stored
+    async fn load(&self, topic: &str, partition: i32) -> anyhow::Result<Option<i64>>;
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::{ConsumedOffset, OffsetStore};
+    use async_trait::async_trait;
+    use sqlx::{PgPool, Postgres, Transaction};
+
+    /// [`OffsetStore`] that keeps offsets per consumer group in the `consumer_offset` table
+    ///
+    /// Handlers should call [`PgOffsetStore::store`] inside the same transaction that writes
+    /// their projection. The table is created by the claims-core migrations
+    /// (see [`crate::postgres::migrate`]).
+    #[derive(Clone)]
+    pub struct PgOffsetStore {
+        pool: PgPool,
+        group_id: String,
+    }
+
+    impl PgOffsetStore {
+        pub fn new<S: AsRef<str>>(pool: PgPool, group_id: S) -> Self {
+            Self {
+                pool,
+                group_id: group_id.as_ref().into(),
+            }
+        }
+
+        /// Stores the offset following `offset` as part of the given transaction
+        pub async fn store(
+            &self,
+            tx: &mut Transaction<'_, Postgres>,
+            offset: &ConsumedOffset,
+        ) -> anyhow::Result<()> {
+            sqlx::query(
+                r#"INSERT INTO consumer_offset (group_id, topic, "partition", "offset") VALUES ($1, $2, $3, $4)
+                ON CONFLICT (group_id, topic, "partition") DO UPDATE SET "offset" = EXCLUDED."offset""#,
+            )
+            .bind(&self.group_id)
+            .bind(&offset.topic)
+            .bind(offset.partition)
+            .bind(offset.next())
+            // In 0.7, `Transaction` can no longer implement `Executor` directly,
+            // so it must be de referenced to the internal connection type.
+            .execute(&mut **tx)
+            .await?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl OffsetStore for PgOffsetStore {
+        async fn load(&self, topic: &str, partition: i32) -> anyhow::Result<Option<i64>> {
+            let row: Option<(i64,)> = sqlx::query_as(
+                r#"SELECT "offset" FROM consumer_offset WHERE group_id = $1 AND topic = $2 AND "partition" = $3"#,
+            )
+            .bind(&self.group_id)
+            .bind(topic)
+            .bind(partition)
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(|(offset,)| offset))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConsumedOffset;
+    use rdkafka::message::{OwnedMessage, Timestamp};
+
+    #[test]
+    fn resumes_after_the_consumed_message() {
+        let message = OwnedMessage::new(
+            None,
+            None,
+            "claims".into(),
+            Timestamp::NotAvailable,
+            3,
+            41,
+            None,
+        );
+        let offset = ConsumedOffset::from_message(&message);
+
+        assert_eq!(
+            offset,
+            ConsumedOffset {
+                topic: "claims".into(),
+                partition: 3,
+                offset: 41
+            }
+        );
+        assert_eq!(offset.next(), 42);
+    }
+}
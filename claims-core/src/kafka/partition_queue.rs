@@ -0,0 +1,142 @@
+use rdkafka::message::OwnedMessage;
+use rdkafka::Message;
+use std::collections::{HashMap, VecDeque};
+
+/// Messages received by a consumer but not handled yet.
+///
+/// Messages are handed out in the order they were received, while the number of queued
+/// messages is bounded per partition. Once a partition reaches `capacity` it should be paused,
+/// and resumed once it drains down to half of it.
+pub struct PartitionQueues {
+    messages: VecDeque<OwnedMessage>,
+    partitions: HashMap<(String, i32), PartitionState>,
+    capacity: usize,
+}
+
+#[derive(Default)]
+struct PartitionState {
+    queued: usize,
+    paused: bool,
+}
+
+/// Change of a partition state caused by a [`PartitionQueues`] operation
+#[derive(Debug, PartialEq, Eq)]
+pub enum FlowControl {
+    Pause(String, i32),
+    Resume(String, i32),
+}
+
+impl PartitionQueues {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            partitions: HashMap::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Queues a message, returns [`FlowControl::Pause`] if its partition became full
+    pub fn push(&mut self, message: OwnedMessage) -> Option<FlowControl> {
+        let key = (message.topic().to_owned(), message.partition());
+        self.messages.push_back(message);
+
+        let state = self.partitions.entry(key.clone()).or_default();
+        state.queued += 1;
+        if !state.paused && state.queued >= self.capacity {
+            state.paused = true;
+            return Some(FlowControl::Pause(key.0, key.1));
+        }
+        None
+    }
+
+    /// Takes the oldest message, alongside [`FlowControl::Resume`] if its partition drained enough
+    pub fn pop(&mut self) -> Option<(OwnedMessage, Option<FlowControl>)> {
+        let message = self.messages.pop_front()?;
+        let key = (message.topic().to_owned(), message.partition());
+
+        let mut flow = None;
+        if let Some(state) = self.partitions.get_mut(&key) {
+            state.queued = state.queued.saturating_sub(1);
+            if state.paused && state.queued <= self.capacity / 2 {
+                state.paused = false;
+                flow = Some(FlowControl::Resume(key.0, key.1));
+            }
+        }
+        Some((message, flow))
+    }
+
+    /// Drops all queued messages of a partition (eg. when the partition is revoked)
+    pub fn remove(&mut self, topic: &str, partition: i32) {
+        if self
+            .partitions
+            .remove(&(topic.to_owned(), partition))
+            .is_some()
+        {
+            self.messages
+                .retain(|m| m.topic() != topic || m.partition() != partition);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FlowControl, PartitionQueues};
+    use rdkafka::message::OwnedMessage;
+    use rdkafka::{Message, Timestamp};
+
+    fn message(partition: i32, offset: i64) -> OwnedMessage {
+        OwnedMessage::new(
+            None,
+            None,
+            "topic".into(),
+            Timestamp::NotAvailable,
+            partition,
+            offset,
+            None,
+        )
+    }
+
+    #[test]
+    fn pauses_full_partition_and_resumes_when_drained() {
+        let mut queues = PartitionQueues::new(4);
+
+        for offset in 0..3 {
+            assert_eq!(queues.push(message(0, offset)), None);
+        }
+        assert_eq!(queues.push(message(1, 0)), None);
+        assert_eq!(
+            queues.push(message(0, 3)),
+            Some(FlowControl::Pause("topic".into(), 0))
+        );
+
+        // Partition 0 stays above the low watermark after the first pop
+        assert_eq!(queues.pop().unwrap().1, None);
+        let (_, flow) = queues.pop().unwrap();
+        assert_eq!(flow, Some(FlowControl::Resume("topic".into(), 0)));
+        assert_eq!(queues.len(), 3);
+    }
+
+    #[test]
+    fn keeps_receive_order_and_drops_removed_partitions() {
+        let mut queues = PartitionQueues::new(10);
+        queues.push(message(0, 0));
+        queues.push(message(1, 0));
+        queues.push(message(0, 1));
+
+        queues.remove("topic", 1);
+
+        let (first, _) = queues.pop().unwrap();
+        let (second, _) = queues.pop().unwrap();
+        assert_eq!((first.partition(), first.offset()), (0, 0));
+        assert_eq!((second.partition(), second.offset()), (0, 1));
+        assert!(queues.is_empty());
+    }
+}
@@ -1,21 +1,45 @@
+use crate::kafka::context::ProtoConsumerContext;
+use crate::kafka::dedup::{DedupStore, EventId};
+use crate::kafka::envelope::{self, Envelope};
+use crate::kafka::offset_store::{ConsumedOffset, OffsetStore};
+use crate::kafka::partition_queue::{FlowControl, PartitionQueues};
+use crate::kafka::trace_context::HeaderExtractor;
+use crate::metrics::{
+    CONSUMER_DUPLICATES, CONSUMER_ERRORS, CONSUMER_HANDLER_DURATION, CONSUMER_MESSAGES,
+    CONSUMER_QUEUED, STATISTICS_INTERVAL_MS,
+};
+use crate::otel;
+use crate::shutdown::ShutdownToken;
 use anyhow::{anyhow, Context};
 use protobuf::Message;
 use rdkafka::config::RDKafkaLogLevel;
 use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
-use rdkafka::{ClientConfig, Message as KafkaMessage};
+use rdkafka::message::OwnedMessage;
+use rdkafka::{ClientConfig, Message as KafkaMessage, Offset, TopicPartitionList};
 use schema_registry_converter::async_impl::easy_proto_raw::EasyProtoRawDecoder;
 use schema_registry_converter::async_impl::schema_registry::SrSettings;
 use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::Notify;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Default number of received messages kept per partition before pausing the partition
+pub const DEFAULT_QUEUE_CAPACITY: usize = 100;
 
 pub struct ProtoConsumer {
-    consumer: StreamConsumer,
+    consumer: StreamConsumer<ProtoConsumerContext>,
     proto_decoder: EasyProtoRawDecoder,
     topic: String,
+    dedup: Option<Arc<dyn DedupStore>>,
+    queue_capacity: usize,
+    shutdown: Option<ShutdownToken>,
 }
 
 impl ProtoConsumer {
     pub fn new<S: AsRef<str>>(
-        consumer: StreamConsumer,
+        consumer: StreamConsumer<ProtoConsumerContext>,
         proto_decoder: EasyProtoRawDecoder,
         topic: S,
     ) -> Self {
@@ -23,34 +47,311 @@ impl ProtoConsumer {
             consumer,
             proto_decoder,
             topic: topic.as_ref().into(),
+            dedup: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            shutdown: None,
         }
     }
 
+    /// Skips messages whose [`EventId`] is already contained in the given [`DedupStore`],
+    /// so that handlers are invoked only once per logical event.
+    ///
+    /// The event is marked once its handler succeeded, outside of the handler's own transaction,
+    /// so an event redelivered after a crash in between is handled again.
+    pub fn with_dedup(mut self, store: Arc<dyn DedupStore>) -> Self {
+        self.dedup = Some(store);
+        self
+    }
+
+    /// Sets the number of received messages kept per partition while the handler is busy.
+    ///
+    /// A partition is paused once its queue is full and resumed once half of it is drained,
+    /// so that slow handlers never stop the consumer from polling (see `max.poll.interval.ms`).
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity;
+        self
+    }
+
+    /// Assigns every partition at the offset kept in the given [`OffsetStore`], as the
+    /// partitions are assigned and so before any of their messages is fetched.
+    ///
+    /// The handlers of [`ProtoConsumer::consume_envelope`] receive the [`ConsumedOffset`] of each
+    /// message in the envelope, so that they can persist it together with their own state
+    /// (see `PgOffsetStore::store`). Offsets are still committed to kafka, but only as a fallback
+    /// for partitions without a stored offset.
+    pub fn with_offset_store(self, store: Arc<dyn OffsetStore>) -> anyhow::Result<Self> {
+        self.consumer.context().set_offset_store(store)?;
+        Ok(self)
+    }
+
+    /// Stops consuming once the token is cancelled, after the message in progress is handled
+    /// and its offset committed.
+    pub fn with_shutdown(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = Some(token);
+        self
+    }
+
     pub async fn consume<M, H, Fut>(&self, handler: H) -> anyhow::Result<()>
     where
         M: Message,
         H: Fn(M) -> Fut,
         Fut: Future<Output = anyhow::Result<()>>,
     {
+        self.run(|e| handler(e.payload)).await
+    }
+
+    /// Consumes messages like [`ProtoConsumer::consume`] but hands each message to the handler
+    /// in an [`Envelope`], along with its event id, correlation id and offset.
+    pub async fn consume_envelope<M, H, Fut>(&self, handler: H) -> anyhow::Result<()>
+    where
+        M: Message,
+        H: Fn(Envelope<M>) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        self.run(handler).await
+    }
+
+    /// Receives and handles messages concurrently until kafka or the handler fails, or shutdown
+    async fn run<M, H, Fut>(&self, handler: H) -> anyhow::Result<()>
+    where
+        M: Message,
+        H: Fn(Envelope<M>) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        self.subscribe()?;
+
+        let queues = Mutex::new(PartitionQueues::new(self.queue_capacity));
+        let queued = Notify::new();
+
+        let result = tokio::select! {
+            result = self.receive(&queues, &queued) => result,
+            result = self.work(&queues, &queued, &handler) => result,
+        };
+
+        if self.is_stopping() {
+            tracing::info!("Consumer of {} stopped", self.topic);
+        }
+        result
+    }
+
+    /// Keeps polling kafka and queues the received messages, pausing the partitions that are full
+    async fn receive(
+        &self,
+        queues: &Mutex<PartitionQueues>,
+        queued: &Notify,
+    ) -> anyhow::Result<()> {
+        loop {
+            let message = tokio::select! {
+                message = self.consumer.recv() => message,
+                // A partition was left unassigned as its stored offset could not be loaded
+                e = self.consumer.context().failed() => return Err(e),
+            };
+            let Ok(message) = message else {
+                break;
+            };
+
+            let flow = {
+                let mut queues = self.lock(queues);
+                let flow = queues.push(message.detach());
+                metrics::gauge!(CONSUMER_QUEUED, queues.len() as f64, "topic" => self.topic.clone());
+                flow
+            };
+            if let Some(flow) = flow {
+                self.flow_control(flow);
+            }
+            queued.notify_one();
+        }
+
+        // TODO handle kafka error - we end up here only if the stream is closed or there is a kafka error (return error?)
+        Ok(())
+    }
+
+    /// Handles the queued messages one by one, resuming the partitions that drained enough
+    async fn work<M, H, Fut>(
+        &self,
+        queues: &Mutex<PartitionQueues>,
+        queued: &Notify,
+        handler: &H,
+    ) -> anyhow::Result<()>
+    where
+        M: Message,
+        H: Fn(Envelope<M>) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        loop {
+            let next = {
+                let mut queues = self.lock(queues);
+                let next = queues.pop();
+                metrics::gauge!(CONSUMER_QUEUED, queues.len() as f64, "topic" => self.topic.clone());
+                next
+            };
+            let Some((message, flow)) = next else {
+                tokio::select! {
+                    _ = queued.notified() => continue,
+                    _ = self.stopping() => return Ok(()),
+                }
+            };
+
+            if let Some(flow) = flow {
+                self.flow_control(flow);
+            }
+
+            let started = Instant::now();
+            let result = self.process(&message, handler).await;
+            metrics::histogram!(CONSUMER_HANDLER_DURATION, started.elapsed(), "topic" => self.topic.clone());
+            if result.is_err() {
+                metrics::increment_counter!(CONSUMER_ERRORS, "topic" => self.topic.clone());
+            }
+            result?;
+
+            metrics::increment_counter!(
+                CONSUMER_MESSAGES,
+                "topic" => self.topic.clone(),
+                "partition" => message.partition().to_string()
+            );
+
+            if self.is_stopping() {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn process<M, H, Fut>(&self, message: &OwnedMessage, handler: &H) -> anyhow::Result<()>
+    where
+        M: Message,
+        H: Fn(Envelope<M>) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        tracing::trace!("Begin handling message {}", message.offset());
+        let offset = ConsumedOffset::from_message(message);
+        let event_id = EventId::from_message(message);
+
+        let correlation_id = envelope::correlation_id(message);
+
+        // Continue the trace of the request that produced the event (if any)
+        let span = tracing::info_span!(
+            "consume",
+            topic = %offset.topic,
+            partition = offset.partition,
+            offset = offset.offset,
+            event_id = %event_id,
+            correlation_id = tracing::field::Empty
+        );
+        if let Some(correlation_id) = &correlation_id {
+            span.record("correlation_id", correlation_id.as_str());
+        }
+        if let Some(headers) = message.headers() {
+            span.set_parent(otel::extract(&HeaderExtractor(headers)));
+        }
+
+        async {
+            if !self.is_duplicate(&event_id).await? {
+                let parsed_payload = self.decode(message).await?;
+
+                let handled = handler(Envelope::new(message, parsed_payload));
+                match correlation_id {
+                    Some(correlation_id) => correlation_id.scope(handled).await?,
+                    None => handled.await?,
+                }
+                self.mark_processed(&event_id).await?;
+            }
+            anyhow::Ok(())
+        }
+        .instrument(span)
+        .await?;
+
+        // Commit the offsets
+        self.commit(&offset)
+    }
+
+    fn is_stopping(&self) -> bool {
+        self.shutdown
+            .as_ref()
+            .is_some_and(ShutdownToken::is_cancelled)
+    }
+
+    /// Resolves once shutdown begins, never without a shutdown token
+    async fn stopping(&self) {
+        match &self.shutdown {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    fn subscribe(&self) -> anyhow::Result<()> {
         self.consumer
             .subscribe(&[&self.topic])
-            .context(format!("Can't subscribe to topic {}", self.topic))?;
+            .context(format!("Can't subscribe to topic {}", self.topic))
+    }
 
-        while let Ok(message) = self.consumer.recv().await {
-            tracing::trace!("Begin handling message {}", message.offset());
+    /// Locks the queues, dropping first the messages of partitions revoked in the meantime
+    fn lock<'q>(
+        &self,
+        queues: &'q Mutex<PartitionQueues>,
+    ) -> std::sync::MutexGuard<'q, PartitionQueues> {
+        let mut queues = queues.lock().expect("Poisoned partition queues lock");
+        for (topic, partition) in self.consumer.context().take_revoked() {
+            queues.remove(&topic, partition);
+        }
+        queues
+    }
 
-            let decoded_payload = self.proto_decoder.decode(message.payload()).await?;
-            let decoded_payload = decoded_payload.ok_or(anyhow!("Unable to decode payload"))?;
+    fn flow_control(&self, flow: FlowControl) {
+        let mut tpl = TopicPartitionList::new();
+        let result = match &flow {
+            FlowControl::Pause(topic, partition) => {
+                tracing::debug!("Queue of {}[{}] is full, pausing", topic, partition);
+                tpl.add_partition(topic, *partition);
+                self.consumer.pause(&tpl)
+            }
+            FlowControl::Resume(topic, partition) => {
+                tracing::debug!("Queue of {}[{}] drained, resuming", topic, partition);
+                tpl.add_partition(topic, *partition);
+                self.consumer.resume(&tpl)
+            }
+        };
 
-            let parsed_payload = Message::parse_from_bytes(&decoded_payload.bytes)?;
+        if let Err(e) = result {
+            tracing::warn!("Unable to apply {:?}: {}", flow, e);
+        }
+    }
 
-            handler(parsed_payload).await?;
+    fn commit(&self, offset: &ConsumedOffset) -> anyhow::Result<()> {
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(
+            &offset.topic,
+            offset.partition,
+            Offset::Offset(offset.next()),
+        )?;
+        self.consumer.commit(&tpl, CommitMode::Async)?;
+        Ok(())
+    }
+
+    async fn decode<M: Message>(&self, message: &OwnedMessage) -> anyhow::Result<M> {
+        let decoded_payload = self.proto_decoder.decode(message.payload()).await?;
+        let decoded_payload = decoded_payload.ok_or(anyhow!("Unable to decode payload"))?;
+
+        let parsed_payload = Message::parse_from_bytes(&decoded_payload.bytes)?;
+        Ok(parsed_payload)
+    }
 
-            // Commit the offsets
-            self.consumer.commit_message(&message, CommitMode::Async)?;
+    async fn is_duplicate(&self, event_id: &EventId) -> anyhow::Result<bool> {
+        let Some(dedup) = &self.dedup else {
+            return Ok(false);
+        };
+
+        let duplicate = dedup.contains(event_id).await?;
+        if duplicate {
+            tracing::debug!("Skipping already processed event {}", event_id);
+            metrics::increment_counter!(CONSUMER_DUPLICATES, "topic" => self.topic.clone());
         }
+        Ok(duplicate)
+    }
 
-        // TODO handle kafka error - we end up here only if the stream is closed or there is a kafka error (return error?)
+    async fn mark_processed(&self, event_id: &EventId) -> anyhow::Result<()> {
+        if let Some(dedup) = &self.dedup {
+            dedup.insert(event_id).await?;
+        }
         Ok(())
     }
 }
@@ -61,14 +362,15 @@ pub fn get_consumer<S: AsRef<str>>(
     group_id: S,
     topic: S,
 ) -> ProtoConsumer {
-    let consumer: StreamConsumer = ClientConfig::new()
+    let consumer: StreamConsumer<ProtoConsumerContext> = ClientConfig::new()
         .set("group.id", group_id.as_ref())
         .set("bootstrap.servers", brokers.as_ref())
         .set("session.timeout.ms", "6000")
         .set("enable.auto.commit", "false")
         .set("auto.offset.reset", "earliest") // Default start from the start of the stream
+        .set("statistics.interval.ms", STATISTICS_INTERVAL_MS) // Feeds the consumer lag metrics
         .set_log_level(RDKafkaLogLevel::Debug)
-        .create()
+        .create_with_context(ProtoConsumerContext::default())
         .expect("Consumer creation error");
 
     let settings = SrSettings::new(schema_registry_url.as_ref().into());
@@ -1,7 +1,8 @@
 use crate::proto_encode::encoder::ProtoEncoder;
 use crate::proto_encode::message::ProtoMessage;
+use crate::shutdown::ShutdownCoordinator;
 use anyhow::Context;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::ClientConfig;
 use schema_registry_converter::async_impl::easy_proto_raw::EasyProtoRawEncoder;
 use schema_registry_converter::async_impl::schema_registry::SrSettings;
@@ -52,6 +53,25 @@ impl ProtoProducer {
             .context("Failed to send kafka message")?;
         Ok(())
     }
+
+    /// Waits for the messages in flight to be delivered, to be called on shutdown
+    pub async fn flush(&self, timeout: Duration) -> anyhow::Result<()> {
+        flush(self.producer.clone(), timeout).await
+    }
+
+    /// Registers a shutdown hook flushing the messages in flight within the shutdown deadline,
+    /// once the components producing them have stopped
+    pub fn flush_on_shutdown(&self, shutdown: &mut ShutdownCoordinator) {
+        let producer = self.producer.clone();
+        let timeout = shutdown.deadline();
+        shutdown.on_shutdown("kafka producer", flush(producer, timeout));
+    }
+}
+
+async fn flush(producer: FutureProducer, timeout: Duration) -> anyhow::Result<()> {
+    tokio::task::spawn_blocking(move || producer.flush(timeout))
+        .await?
+        .context("Failed to flush kafka producer")
 }
 
 pub fn get_producer<S: AsRef<str>>(brokers: S, schema_registry_url: S) -> ProtoProducer {
@@ -68,3 +88,22 @@ pub fn get_producer<S: AsRef<str>>(brokers: S, schema_registry_url: S) -> ProtoP
 
     ProtoProducer::new(producer, proto_encoder)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::get_producer;
+    use crate::shutdown::ShutdownCoordinator;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn flushes_on_shutdown() {
+        let mut shutdown = ShutdownCoordinator::new(Duration::from_secs(1));
+        // Stops at once, triggering the shutdown
+        shutdown.spawn("stopped", |_| async { Ok(()) });
+        let producer = get_producer("localhost:1", "http://localhost:1");
+        producer.flush_on_shutdown(&mut shutdown);
+
+        // Nothing is in flight, so the flush completes within the deadline
+        shutdown.run().await.unwrap();
+    }
+}
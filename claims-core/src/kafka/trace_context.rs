@@ -0,0 +1,21 @@
+use opentelemetry::propagation::Extractor;
+use rdkafka::message::Headers;
+
+/// Reads the W3C trace context placed by the debezium outbox event router in the message headers
+/// (see [`otel::extract`](crate::otel::extract))
+pub struct HeaderExtractor<'a, H: Headers>(pub &'a H);
+
+impl<H: Headers> Extractor for HeaderExtractor<'_, H> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|h| h.key.eq_ignore_ascii_case(key))
+            .and_then(|h| h.value)
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .map(|v| v.trim_matches('"'))
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.iter().map(|h| h.key).collect()
+    }
+}
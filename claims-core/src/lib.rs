@@ -1,5 +1,12 @@
+#[cfg(feature = "axum")]
+pub mod admin;
 pub mod config;
+pub mod correlation;
 pub mod kafka;
+pub mod metrics;
+pub mod otel;
+#[cfg(feature = "postgres")]
+pub mod postgres;
 pub mod proto_encode;
 pub mod shutdown;
 pub mod tracing;
@@ -0,0 +1,74 @@
+//! Names of the metrics recorded by claims core components.
+//!
+//! Metrics are recorded through the [`metrics`] facade, so they are exported by whatever
+//! recorder (registry) the service installs. Throughput (messages/sec) is derived from the
+//! counters by the metrics backend, eg. `rate(kafka_consumer_messages_total[1m])`.
+use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+
+pub use metrics_exporter_prometheus::PrometheusHandle;
+
+/// Messages handled by a consumer, labeled by `topic` and `partition`
+pub const CONSUMER_MESSAGES: &str = "kafka_consumer_messages_total";
+/// Messages skipped as already processed, labeled by `topic`
+pub const CONSUMER_DUPLICATES: &str = "kafka_consumer_duplicates_total";
+/// Messages whose handling failed, labeled by `topic`
+pub const CONSUMER_ERRORS: &str = "kafka_consumer_errors_total";
+/// Time spent decoding and handling a message, labeled by `topic`
+pub const CONSUMER_HANDLER_DURATION: &str = "kafka_consumer_handler_duration_seconds";
+/// Messages received but not yet handled, labeled by `topic`
+pub const CONSUMER_QUEUED: &str = "kafka_consumer_queued_messages";
+/// Difference between the high watermark and the committed offset (from rdkafka statistics),
+/// labeled by `topic` and `partition`
+pub const CONSUMER_LAG: &str = "kafka_consumer_lag";
+
+/// Time spent encoding a message through the schema registry, labeled by `schema`
+pub const SCHEMA_REGISTRY_ENCODE_DURATION: &str = "schema_registry_encode_duration_seconds";
+
+/// Interval of rdkafka statistics emission, feeding [`CONSUMER_LAG`]
+pub const STATISTICS_INTERVAL_MS: &str = "5000";
+
+/// Buckets (in seconds) of all `*_duration_seconds` histograms
+const DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Installs a prometheus recorder as the global metrics recorder.
+///
+/// The returned handle renders the recorded metrics in the prometheus exposition format,
+/// to be served by the `/metrics` endpoint of the service.
+pub fn install_prometheus() -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("duration_seconds".into()), DURATION_BUCKETS)?
+        .install_recorder()?;
+    describe();
+    Ok(handle)
+}
+
+/// Registers the descriptions of claims core metrics with the installed recorder
+pub fn describe() {
+    describe_counter!(CONSUMER_MESSAGES, "Messages handled by a consumer");
+    describe_counter!(
+        CONSUMER_DUPLICATES,
+        "Messages skipped by a consumer as already processed"
+    );
+    describe_counter!(CONSUMER_ERRORS, "Messages whose handling failed");
+    describe_histogram!(
+        CONSUMER_HANDLER_DURATION,
+        Unit::Seconds,
+        "Time spent decoding and handling a message"
+    );
+    describe_gauge!(
+        CONSUMER_QUEUED,
+        "Messages received by a consumer but not yet handled"
+    );
+    describe_gauge!(
+        CONSUMER_LAG,
+        "Difference between the partition high watermark and the committed offset"
+    );
+    describe_histogram!(
+        SCHEMA_REGISTRY_ENCODE_DURATION,
+        Unit::Seconds,
+        "Time spent encoding a message through the schema registry"
+    );
+}
@@ -0,0 +1,139 @@
+//! OpenTelemetry export of the tracing spans and W3C trace context propagation.
+//!
+//! The trace context crosses process boundaries as `traceparent` / `tracestate` headers
+//! (see: https://www.w3.org/TR/trace-context/), eg. from a REST request to the outbox table
+//! and from there (through debezium) to the kafka message headers.
+use crate::config::{Otel, OtelExporter};
+use anyhow::Context as _;
+use futures::future::BoxFuture;
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::trace::{self, Tracer, TracerProvider};
+use opentelemetry::sdk::Resource;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use serde_json::json;
+use std::fmt::Debug;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::SystemTime;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// Installs the W3C trace context propagator used by [`inject`] and [`extract`]
+pub fn init_propagator() {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+}
+
+/// Installs the global tracer provider exporting to the configured [`OtelExporter`]
+pub fn tracer(config: &Otel) -> anyhow::Result<Tracer> {
+    let trace_config = trace::config().with_resource(Resource::new([KeyValue::new(
+        "service.name",
+        config.service.clone(),
+    )]));
+
+    let tracer = match &config.exporter {
+        OtelExporter::Otlp { endpoint } => opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .with_trace_config(trace_config)
+            .install_batch(opentelemetry::runtime::Tokio)
+            .context("Unable to install otlp exporter")?,
+        OtelExporter::Stdout => {
+            install_simple(JsonSpanExporter::new(std::io::stdout()), trace_config)
+        }
+        OtelExporter::File { path } => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .context(format!("Unable to open spans file {path}"))?;
+            install_simple(JsonSpanExporter::new(file), trace_config)
+        }
+    };
+    Ok(tracer)
+}
+
+/// Flushes the pending spans, to be called before the process exits
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+/// Writes the trace context of the span to the carrier (eg. message or outbox headers)
+pub fn inject(span: &tracing::Span, carrier: &mut dyn Injector) {
+    let context = span.context();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, carrier));
+}
+
+/// Reads the remote trace context from the carrier, to be set as a span parent
+pub fn extract(carrier: &dyn Extractor) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(carrier))
+}
+
+fn install_simple<E: SpanExporter + 'static>(exporter: E, config: trace::Config) -> Tracer {
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter)
+        .with_config(config)
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    let _ = global::set_tracer_provider(provider);
+    tracer
+}
+
+/// Exports every finished span as a json line, meant for local development and tests
+#[derive(Debug)]
+pub struct JsonSpanExporter<W> {
+    writer: W,
+}
+
+impl<W: Write + Send + Debug> JsonSpanExporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    fn write(&mut self, span: &SpanData) -> std::io::Result<()> {
+        let attributes: serde_json::Map<_, _> = span
+            .attributes
+            .iter()
+            .map(|(k, v)| (k.as_str().to_owned(), json!(v.as_str())))
+            .collect();
+
+        let line = json!({
+            "traceId": span.span_context.trace_id().to_string(),
+            "spanId": span.span_context.span_id().to_string(),
+            "parentSpanId": span.parent_span_id.to_string(),
+            "name": span.name,
+            "startTimeUnixNano": unix_nanos(span.start_time),
+            "endTimeUnixNano": unix_nanos(span.end_time),
+            "attributes": attributes,
+        });
+
+        serde_json::to_writer(&mut self.writer, &line)?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+impl<W: Write + Send + Debug> SpanExporter for JsonSpanExporter<W> {
+    fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+        let result: ExportResult = batch
+            .iter()
+            .try_for_each(|span| self.write(span))
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Unable to write spans: {e}").into());
+        Box::pin(std::future::ready(result))
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
@@ -0,0 +1,88 @@
+use crate::config::{Database, DatabaseSslMode};
+use anyhow::Context;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Embedded migrations of the tables kept by claims-core, the `consumer_offset` table of
+/// [`PgOffsetStore`](crate::kafka::offset_store::PgOffsetStore) and the `consumer_processed_event`
+/// table of [`PgDedupStore`](crate::kafka::dedup::PgDedupStore).
+///
+/// Their versions start at 1000000 to stay apart from the migrations of the services,
+/// apply both with [`migrate`].
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Runs the migrations of the service along with the [`MIGRATOR`] migrations of claims-core,
+/// as a single set so that neither reports the migrations of the other as missing
+pub async fn migrate(pool: &PgPool, service: &Migrator) -> anyhow::Result<()> {
+    let mut migrations: Vec<_> = service
+        .migrations
+        .iter()
+        .chain(MIGRATOR.migrations.iter())
+        .cloned()
+        .collect();
+    migrations.sort_by_key(|m| m.version);
+
+    let migrator = Migrator {
+        migrations: migrations.into(),
+        ignore_missing: service.ignore_missing,
+        locking: service.locking,
+    };
+    migrator
+        .run(pool)
+        .await
+        .context("Unable to exec db migrations")
+}
+
+/// Connects a pool to the configured database
+pub async fn connect(config: &Database) -> anyhow::Result<PgPool> {
+    pool_options(config)
+        .connect_with(connect_options(config)?)
+        .await
+        .context("Unable to connect to database")
+}
+
+pub fn pool_options(config: &Database) -> PgPoolOptions {
+    let pool = &config.pool;
+    PgPoolOptions::new()
+        .max_connections(pool.max_connections)
+        .min_connections(pool.min_connections)
+        .acquire_timeout(Duration::from_secs(pool.acquire_timeout_secs))
+        .idle_timeout(pool.idle_timeout_secs.map(Duration::from_secs))
+        .max_lifetime(pool.max_lifetime_secs.map(Duration::from_secs))
+        .test_before_acquire(true)
+}
+
+/// Connection options of the url, with the configured overrides applied.
+///
+/// The schema and the statement timeout are sent as startup parameters, so they apply to every
+/// connection (including the reconnections of the pool) without extra round trips.
+pub fn connect_options(config: &Database) -> anyhow::Result<PgConnectOptions> {
+    let mut options = PgConnectOptions::from_str(&config.url).context("Invalid database url")?;
+
+    let mut parameters = vec![];
+    if let Some(schema) = &config.schema {
+        parameters.push(("search_path", schema.clone()));
+    }
+    if let Some(timeout) = config.statement_timeout_ms {
+        parameters.push(("statement_timeout", timeout.to_string()));
+    }
+    options = options.options(parameters);
+
+    if let Some(ssl_mode) = config.ssl_mode {
+        options = options.ssl_mode(match ssl_mode {
+            DatabaseSslMode::Disable => PgSslMode::Disable,
+            DatabaseSslMode::Allow => PgSslMode::Allow,
+            DatabaseSslMode::Prefer => PgSslMode::Prefer,
+            DatabaseSslMode::Require => PgSslMode::Require,
+            DatabaseSslMode::VerifyCa => PgSslMode::VerifyCa,
+            DatabaseSslMode::VerifyFull => PgSslMode::VerifyFull,
+        });
+    }
+    if let Some(root_cert) = &config.ssl_root_cert {
+        options = options.ssl_root_cert(root_cert.as_str());
+    }
+    Ok(options)
+}
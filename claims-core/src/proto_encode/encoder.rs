@@ -2,7 +2,9 @@ use anyhow::Context;
 use async_trait::async_trait;
 use schema_registry_converter::async_impl::easy_proto_raw::EasyProtoRawEncoder;
 use schema_registry_converter::schema_registry_common::SubjectNameStrategy;
+use std::time::Instant;
 
+use crate::metrics::SCHEMA_REGISTRY_ENCODE_DURATION;
 use crate::proto_encode::message::ProtoMessage;
 
 /// Helper struct that holds an encoded [`ProtoMessage`]
@@ -64,6 +66,7 @@ impl ProtoEncoder for EasyProtoRawEncoder {
         payload_strategy: SubjectNameStrategy,
         key_strategy: Option<SubjectNameStrategy>,
     ) -> anyhow::Result<ProtoEncodedMessage> {
+        let started = Instant::now();
         let payload = m.payload()?;
         let full_name = m.full_name().to_owned();
 
@@ -92,6 +95,7 @@ impl ProtoEncoder for EasyProtoRawEncoder {
         };
 
         let encoded_key = key;
+        metrics::histogram!(SCHEMA_REGISTRY_ENCODE_DURATION, started.elapsed(), "schema" => full_name);
         Ok(ProtoEncodedMessage {
             key: encoded_key,
             payload: encoded_value,
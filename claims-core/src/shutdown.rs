@@ -1,3 +1,12 @@
+use anyhow::bail;
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
 /// Handles server graceful termination
 /// Receives termination signals logs the result and begins the termination process
 pub async fn shutdown_signal() {
@@ -21,3 +30,177 @@ pub async fn shutdown_signal() {
 
     tracing::warn!("Signal received, starting graceful shutdown");
 }
+
+/// Notified to a component once the shutdown begins
+#[derive(Clone)]
+pub struct ShutdownToken(watch::Receiver<bool>);
+
+impl ShutdownToken {
+    /// Resolves once the shutdown begins
+    pub async fn cancelled(&self) {
+        let mut receiver = self.0.clone();
+        // The coordinator being dropped also means shutdown
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+}
+
+struct Component {
+    name: String,
+    handle: JoinHandle<anyhow::Result<()>>,
+}
+
+/// Coordinates the graceful shutdown of the service components.
+///
+/// Components (servers, consumers) are spawned with a [`ShutdownToken`] and are expected to stop
+/// once it is cancelled. Shutdown begins on a termination signal or as soon as any component stops,
+/// then all components get up to `deadline` to stop, followed by the shutdown hooks
+/// (eg. producer flush, db pool close) within the same deadline.
+pub struct ShutdownCoordinator {
+    deadline: Duration,
+    trigger: watch::Sender<bool>,
+    stopped: Arc<Notify>,
+    components: Vec<Component>,
+    hooks: Vec<(String, BoxFuture<'static, anyhow::Result<()>>)>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(deadline: Duration) -> Self {
+        let (trigger, _) = watch::channel(false);
+        Self {
+            deadline,
+            trigger,
+            stopped: Arc::new(Notify::new()),
+            components: vec![],
+            hooks: vec![],
+        }
+    }
+
+    /// Time given to the components and then the hooks to complete once shutdown begins
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    pub fn token(&self) -> ShutdownToken {
+        ShutdownToken(self.trigger.subscribe())
+    }
+
+    /// Spawns a component that runs until the given token is cancelled
+    pub fn spawn<F, Fut>(&mut self, name: &str, component: F)
+    where
+        F: FnOnce(ShutdownToken) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let running = component(self.token());
+        let stopped = self.stopped.clone();
+        let handle = tokio::spawn(async move {
+            let result = running.await;
+            stopped.notify_one();
+            result
+        });
+
+        self.components.push(Component {
+            name: name.into(),
+            handle,
+        });
+    }
+
+    /// Registers a hook to run once all components have stopped
+    pub fn on_shutdown<Fut>(&mut self, name: &str, hook: Fut)
+    where
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.hooks.push((name.into(), Box::pin(hook)));
+    }
+
+    /// Waits for a termination signal (or any component to stop) and shuts down all components.
+    ///
+    /// Fails reporting the components that failed or did not stop within the deadline.
+    pub async fn run(self) -> anyhow::Result<()> {
+        tokio::select! {
+            _ = shutdown_signal() => {},
+            _ = self.stopped.notified() => tracing::warn!("A component stopped, starting graceful shutdown"),
+        }
+
+        self.trigger.send_replace(true);
+        let deadline = Instant::now() + self.deadline;
+        let mut failed = vec![];
+        let mut timed_out = vec![];
+
+        for Component { name, mut handle } in self.components {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Ok(Ok(()))) => tracing::info!("Component {} stopped", name),
+                Ok(Ok(Err(e))) => {
+                    tracing::error!("Component {} failed: {:#}", name, e);
+                    failed.push(name);
+                }
+                Ok(Err(e)) => {
+                    tracing::error!("Component {} panicked: {}", name, e);
+                    failed.push(name);
+                }
+                Err(_) => {
+                    handle.abort();
+                    timed_out.push(name);
+                }
+            }
+        }
+
+        for (name, hook) in self.hooks {
+            match tokio::time::timeout_at(deadline, hook).await {
+                Ok(Ok(())) => tracing::info!("Shutdown hook {} completed", name),
+                Ok(Err(e)) => {
+                    tracing::error!("Shutdown hook {} failed: {:#}", name, e);
+                    failed.push(name);
+                }
+                Err(_) => timed_out.push(name),
+            }
+        }
+
+        if !timed_out.is_empty() {
+            tracing::error!(
+                "Did not stop within {:?}: {}",
+                self.deadline,
+                timed_out.join(", ")
+            );
+        }
+
+        match (failed.is_empty(), timed_out.is_empty()) {
+            (true, true) => Ok(()),
+            _ => bail!(
+                "Unclean shutdown, failed: [{}], timed out: [{}]",
+                failed.join(", "),
+                timed_out.join(", ")
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_components_not_stopping_within_deadline() {
+        let mut shutdown = ShutdownCoordinator::new(Duration::from_millis(50));
+        shutdown.spawn("graceful", |token| async move {
+            token.cancelled().await;
+            Ok(())
+        });
+        shutdown.spawn("stuck", |_| async {
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+        // Stops at once, triggering the shutdown of the others
+        shutdown.spawn("failing", |_| async { bail!("boom") });
+
+        let error = shutdown.run().await.unwrap_err().to_string();
+
+        assert_eq!(
+            error,
+            "Unclean shutdown, failed: [failing], timed out: [stuck]"
+        );
+    }
+}
@@ -1,33 +1,164 @@
-use crate::config;
+use crate::config::{LogFile, LogFormat, LogLevel, LogRotation};
+use crate::{config, otel};
+use anyhow::Context;
+use opentelemetry::sdk::trace::Tracer;
+use tracing::Subscriber;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Directive;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::Layered;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
 
-pub fn init(config: &config::Log) -> anyhow::Result<()> {
-    init_log_and_tracing(|mut e| {
-        // Configure root level
-        if let Some(root_level) = &config.level.root {
-            e = e.add_directive(root_level.parse().unwrap_or_default())
-        }
+/// A log output layer, see [`fmt_layer`]
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
-        // Configure specific directives
-        for directive in &config.level.directives {
-            let directive_string = format!("{}={}", directive.namespace, directive.level);
-            e = e.add_directive(directive_string.parse().unwrap_or_default());
-        }
-        e
-    })
+/// Handle to the log filter of the running process, see [`LogLevelHandle::reload`]
+#[derive(Clone)]
+pub struct LogLevelHandle(reload::Handle<EnvFilter, Layered<Vec<BoxedLayer>, Registry>>);
+
+impl LogLevelHandle {
+    /// The directives currently in effect, eg. `warn,app_claims_service=debug`
+    pub fn current(&self) -> anyhow::Result<String> {
+        self.0
+            .with_current(|filter| filter.to_string())
+            .context("Unable to read the log filter")
+    }
+
+    /// Replaces all the directives in effect (including the configured ones) with the given ones
+    pub fn reload(&self, filter: EnvFilter) -> anyhow::Result<()> {
+        self.0
+            .reload(filter)
+            .context("Unable to reload the log filter")
+    }
+
+    /// Parses comma separated directives in the `RUST_LOG` syntax
+    pub fn parse(directives: &str) -> anyhow::Result<EnvFilter> {
+        EnvFilter::try_new(directives)
+            .with_context(|| format!("Invalid log directives `{directives}`"))
+    }
+}
+
+/// Installs the configured log outputs, returning the handle to change the log levels at runtime
+pub fn init(config: &config::Log) -> anyhow::Result<LogLevelHandle> {
+    otel::init_propagator();
+    let tracer = config.otel.as_ref().map(otel::tracer).transpose()?;
+    let rust_log = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => Some(directives),
+        Err(std::env::VarError::NotPresent) => None,
+        Err(e) => return Err(e).context(format!("Invalid {}", EnvFilter::DEFAULT_ENV)),
+    };
+    let env_filter = env_filter(&config.level, rust_log.as_deref())?;
+
+    let mut layers = vec![fmt_layer(config.format, std::io::stdout, true)];
+    if let Some(file) = &config.file {
+        layers.push(fmt_layer(file.format, file_appender(file)?, false));
+    }
+
+    init_log_and_tracing(layers, tracer, env_filter)
+}
+
+/// Installs the log output layers filtered by the (reloadable) `env_filter` and, given a tracer,
+/// the OpenTelemetry layer exporting the spans
+pub fn init_log_and_tracing(
+    mut layers: Vec<BoxedLayer>,
+    tracer: Option<Tracer>,
+    env_filter: EnvFilter,
+) -> anyhow::Result<LogLevelHandle> {
+    if let Some(tracer) = tracer {
+        layers.push(tracing_opentelemetry::layer().with_tracer(tracer).boxed());
+    }
+
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    let subscriber = tracing_subscriber::registry().with(layers).with(env_filter);
+
+    tracing::subscriber::set_global_default(subscriber)?;
+    Ok(LogLevelHandle(handle))
+}
+
+/// Builds the filter of the configured levels on top of the given `RUST_LOG` directives (if set),
+/// failing on the first directive that cannot be parsed (including the `RUST_LOG` ones)
+pub fn env_filter(level: &LogLevel, rust_log: Option<&str>) -> anyhow::Result<EnvFilter> {
+    let mut filter = match rust_log {
+        Some(directives) => EnvFilter::try_new(directives)
+            .with_context(|| format!("Invalid log directives in {}", EnvFilter::DEFAULT_ENV))?,
+        // Only the configured levels apply
+        None => EnvFilter::default(),
+    };
+
+    // Configure root level
+    if let Some(root_level) = &level.root {
+        filter = filter.add_directive(parse_directive(root_level)?);
+    }
+
+    // Configure specific directives
+    for directive in &level.directives {
+        let directive_string = format!("{}={}", directive.namespace, directive.level);
+        filter = filter.add_directive(parse_directive(&directive_string)?);
+    }
+    Ok(filter)
 }
 
-pub fn init_log_and_tracing<T>(env_filter_customizer: T) -> anyhow::Result<()>
+fn parse_directive(directive: &str) -> anyhow::Result<Directive> {
+    directive
+        .parse()
+        .with_context(|| format!("Invalid log directive `{directive}`"))
+}
+
+/// A log output layer in the given format, written to the given writer
+pub fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
 where
-    T: Fn(EnvFilter) -> EnvFilter,
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
 {
-    let fmt_layer = tracing_subscriber::fmt::layer().json();
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
 
-    let subscriber = tracing_subscriber::registry()
-        .with(fmt_layer)
-        .with(env_filter_customizer(EnvFilter::from_default_env()));
+    match format {
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
 
-    tracing::subscriber::set_global_default(subscriber)?;
-    Ok(())
+fn file_appender(config: &LogFile) -> anyhow::Result<RollingFileAppender> {
+    let rotation = match config.rotation {
+        LogRotation::Minutely => Rotation::MINUTELY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+
+    std::fs::create_dir_all(&config.directory)
+        .with_context(|| format!("Unable to create log directory {}", config.directory))?;
+    Ok(RollingFileAppender::new(
+        rotation,
+        &config.directory,
+        &config.prefix,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::env_filter;
+    use crate::config::LogLevel;
+
+    #[test]
+    fn rejects_invalid_rust_log_directives() {
+        let level = LogLevel {
+            root: Some("warn".into()),
+            directives: vec![],
+        };
+
+        let invalid = env_filter(&level, Some("app=notalevel"));
+        let valid = env_filter(&level, Some("app=debug"));
+        let unset = env_filter(&level, None);
+
+        let error = format!("{:#}", invalid.unwrap_err());
+        assert!(error.contains("RUST_LOG"), "{error}");
+        assert!(valid.unwrap().to_string().contains("app=debug"));
+        assert!(unset.is_ok());
+    }
 }
@@ -12,7 +12,16 @@ pub mod proto {
 
 // <editor-fold desc="Claim models">
 #[derive(
-    Clone, Copy, Debug, Default, Serialize, Deserialize, strum::Display, strum::EnumString,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
 )]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -30,6 +39,8 @@ pub mod proto {
         rename_variants = "STREAMING_SNAKE_CASE"
     )
 )]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
 pub enum ClaimStatus {
     #[default]
     Open,
@@ -39,7 +50,16 @@ pub enum ClaimStatus {
 }
 
 #[derive(
-    Clone, Copy, Debug, Default, Serialize, Deserialize, strum::Display, strum::EnumString,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
 )]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -57,6 +77,8 @@ pub enum ClaimStatus {
         rename_variants = "STREAMING_SNAKE_CASE"
     )
 )]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
 pub enum IncidentType {
     #[default]
     OtherDamage,
@@ -68,18 +90,29 @@ pub enum IncidentType {
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "proto", derive(ProtoMap))]
 #[cfg_attr(feature = "proto", proto_map(source = "proto::claim::Claim"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Claim {
     pub id: i32,
     pub claim_no: String,
     pub status: ClaimStatus,
     pub incident_type: IncidentType,
+    pub version: i32,
 }
 
 // </editor-fold>
 
 // <editor-fold desc="Party models">
 #[derive(
-    Clone, Copy, Debug, Default, Serialize, Deserialize, strum::Display, strum::EnumString,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
 )]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -97,6 +130,8 @@ pub struct Claim {
         rename_variants = "STREAMING_SNAKE_CASE"
     )
 )]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
 pub enum PartyType {
     #[default]
     Person,
@@ -104,7 +139,16 @@ pub enum PartyType {
 }
 
 #[derive(
-    Clone, Copy, Debug, Default, Serialize, Deserialize, strum::Display, strum::EnumString,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
 )]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -122,6 +166,8 @@ pub enum PartyType {
         rename_variants = "STREAMING_SNAKE_CASE"
     )
 )]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
 pub enum PartySubtype {
     Car,
     Motorbike,
@@ -137,6 +183,8 @@ pub enum PartySubtype {
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "proto", derive(ProtoMap))]
 #[cfg_attr(feature = "proto", proto_map(source = "proto::party::Party"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 pub struct Party {
     pub id: i32,
     pub claim_id: i32,
@@ -144,6 +192,7 @@ pub struct Party {
     pub r#type: PartyType,
     pub subtype: PartySubtype,
     pub data: PartyData,
+    pub version: i32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -158,6 +207,8 @@ pub struct Party {
         rename_variants = "snake_case"
     )
 )]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Union))]
 pub enum PartyData {
     #[serde(rename = "PERSON")]
     Person(Person),
@@ -185,6 +236,12 @@ impl PartyData {
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "proto", derive(ProtoMap))]
 #[cfg_attr(feature = "proto", proto_map(source = "proto::party::Person"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "graphql",
+    derive(async_graphql::SimpleObject, async_graphql::InputObject),
+    graphql(input_name = "PersonInput")
+)]
 pub struct Person {
     pub subtype: PartySubtype,
     pub name: String,
@@ -194,6 +251,12 @@ pub struct Person {
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "proto", derive(ProtoMap))]
 #[cfg_attr(feature = "proto", proto_map(source = "proto::party::Vehicle"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "graphql",
+    derive(async_graphql::SimpleObject, async_graphql::InputObject),
+    graphql(input_name = "VehicleInput")
+)]
 pub struct Vehicle {
     pub subtype: PartySubtype,
     pub reg_no: String,
@@ -10,8 +10,13 @@ fn main() {
     Codegen::new()
         .pure()
         .out_dir(out_dir)
-        .includes(["resources/proto"])
-        .inputs(["resources/proto/claim.proto", "resources/proto/party.proto"])
+        .includes(["resources/proto", "resources/api"])
+        .inputs([
+            "resources/proto/claim.proto",
+            "resources/proto/party.proto",
+            // Messages of the claims grpc api, not published to the schema registry
+            "resources/api/claims_api.proto",
+        ])
         .customize(
             protobuf_codegen::Customize::default()
                 .generate_accessors(true)
@@ -21,6 +26,7 @@ fn main() {
 
     let mod_file_content = r#"//@generated
 pub mod claim;
+pub mod claims_api;
 pub mod party;
 
 "#;
@@ -10,11 +10,13 @@ use claims_core::kafka::proto_consumer;
 use claims_core::kafka::proto_producer;
 use claims_core::proto_encode::encoder::ProtoEncoder;
 use claims_core::proto_encode::message::MessageKeyPair;
+use claims_core::shutdown::ShutdownCoordinator;
 
 use schema_registry_converter::async_impl::easy_proto_raw::EasyProtoRawEncoder;
 use schema_registry_converter::async_impl::schema_registry::SrSettings;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::fmt::Subscriber;
 
 use claims_schema::protos::claim::Claim;
@@ -53,13 +55,17 @@ async fn main() -> anyhow::Result<()> {
     );
 
     let handler = CountingMessageHandler::default();
+    let mut shutdown = ShutdownCoordinator::new(Duration::from_secs(5));
 
-    // Spawn a task to consume messages
-    let consumer = tokio::spawn(async move {
+    // Spawn a task to consume messages until shutdown
+    shutdown.spawn("claims consumer", |token| async move {
         consumer
+            .with_shutdown(token)
             .consume(|c| async { handler.handle_message(c).await })
             .await
     });
+    // Deliver the messages still in flight on shutdown
+    producer.flush_on_shutdown(&mut shutdown);
 
     // Start to send proto messages on this task
     // Create protobuf entity
@@ -94,9 +100,9 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("Claim message send successfully")
     }
 
-    // Wait for consumer to terminate
-    if let Ok(Err(err)) = consumer.await {
-        tracing::error!("Consumer terminated with error: {}", err);
+    // Wait for a termination signal (or the consumer to terminate)
+    if let Err(err) = shutdown.run().await {
+        tracing::error!("{:#}", err);
     }
     tracing::info!("Main task terminated");
 